@@ -0,0 +1,28 @@
+use nom::{
+    bytes::complete::tag, character::complete::multispace1, combinator::cut, error::context,
+    sequence::terminated,
+};
+
+use crate::{Body, Ident, Parser};
+
+/// A named section of a layout.
+///
+/// block title { "My Page" }
+///
+/// Files extending the layout may override it, otherwise the body is used as it is.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub name: Ident,
+    pub body: Body,
+}
+
+impl Parser for Block {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        let (input, _) = terminated(tag("block"), multispace1)(input)?;
+
+        let (input, name) = context("expected block name", cut(Ident::parse))(input)?;
+        let (input, body) = context("expected block body", cut(Body::parse_trim))(input)?;
+
+        Ok((input, Block { name, body }))
+    }
+}
@@ -1,11 +1,6 @@
-use nom::{
-    branch::alt,
-    combinator::{map, opt},
-    multi::many0,
-    sequence::terminated,
-};
+use nom::{branch::alt, combinator::map, multi::many0, sequence::terminated};
 
-use crate::{keywords::*, Element, Node, Parser};
+use crate::{keywords::*, parser::element_separator, Element, Node, Parser};
 
 pub type Body = Vec<Element>;
 
@@ -15,10 +10,7 @@ impl Parser for Body {
         fn parse_block(input: &str) -> nom::IResult<&str, Body> {
             let (input, _) = KeywordCurlyOpen::parse(input)?;
 
-            let (input, nodes) = many0(terminated(
-                Element::parse_trim,
-                opt(KeywordComma::parse_trim),
-            ))(input)?;
+            let (input, nodes) = many0(terminated(Element::parse_trim, element_separator))(input)?;
 
             let (input, _) = KeywordCurlyClose::parse_trim(input)?;
 
@@ -44,30 +36,6 @@ impl Parser for Body {
         ))(input)
     }
 
-    fn parse_trim(input: &str) -> nom::IResult<&str, Self> {
-        let input = input.trim_start();
-
-        // cut out commments
-        if input.starts_with("//") {
-            if let Some(index) = input.find('\n') {
-                let index = index + 1;
-                return Self::parse_trim(&input[index..]);
-            }
-            return Self::parse_trim("");
-        }
-        /* cut out comments */
-        if input.starts_with("/*") {
-            if let Some(index) = input.find("*/") {
-                let index = index + 2;
-                return Self::parse_trim(&input[index..]);
-            }
-            // It's allowed to simply cut off all remaining content without closing */
-            return Self::parse_trim("");
-        }
-
-        Self::parse(input)
-    }
-
     fn from_s(s: &str) -> Self {
         Self::parse_trim(s).unwrap().1
     }
@@ -0,0 +1,59 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::multispace0,
+    combinator::{map, opt, rest},
+    sequence::{delimited, preceded, terminated},
+};
+
+use crate::{
+    keywords::*,
+    parser::{comment_end, reject_strict},
+    Parser,
+};
+
+/// Comment that ends up in the html output.
+///
+/// /*! kept */
+/// comment "kept"
+///
+/// Both become <!-- kept -->
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct HtmlComment(pub String);
+
+impl Parser for HtmlComment {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        alt((
+            map(
+                preceded(
+                    tag("/*!"),
+                    // Just like other comments, it's allowed to omit the closing */, except in strict mode
+                    alt((
+                        closed,
+                        preceded(|i| reject_strict(i, "unterminated comment"), rest),
+                    )),
+                ),
+                |s: &str| HtmlComment(s.to_string()),
+            ),
+            map(
+                delimited(
+                    terminated(tag("comment"), multispace0),
+                    String::parse,
+                    opt(KeywordNone::parse_trim),
+                ),
+                HtmlComment,
+            ),
+        ))(input)
+    }
+}
+
+/// Content up to the closing */, which may contain nested comments.
+fn closed(input: &str) -> nom::IResult<&str, &str> {
+    match comment_end(input) {
+        Some(end) => Ok((&input[end + 2..], &input[..end])),
+        None => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::TakeUntil,
+        ))),
+    }
+}
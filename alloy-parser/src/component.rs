@@ -0,0 +1,48 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::multispace1,
+    combinator::{cut, opt},
+    error::context,
+    sequence::terminated,
+};
+
+use crate::{Attributes, Body, Ident, Parser};
+
+/// A reusable piece of markup.
+///
+/// component Card(title, href: '#') {
+///     div.card {
+///         h2 "${title}"
+///         slot;
+///     }
+/// }
+///
+/// Parameters use the attribute syntax, a value given in the definition is the default.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    pub name: Ident,
+    pub params: Attributes,
+    pub body: Body,
+    /// Text of the /// comments right before the definition, e.g. to describe the parameters.
+    pub doc: Option<String>,
+}
+
+impl Parser for Component {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        let (input, _) = terminated(tag("component"), multispace1)(input)?;
+
+        let (input, name) = context("expected component name", cut(Ident::parse))(input)?;
+        let (input, params) = opt(Attributes::parse_trim)(input)?;
+        let (input, body) = context("expected component body", cut(Body::parse_trim))(input)?;
+
+        Ok((
+            input,
+            Component {
+                name,
+                params: params.unwrap_or_default(),
+                body,
+                doc: None,
+            },
+        ))
+    }
+}
@@ -0,0 +1,108 @@
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, multispace0, multispace1},
+    combinator::{cut, map, opt},
+    error::context,
+    sequence::{preceded, separated_pair, terminated},
+};
+
+use crate::{Body, Ident, Parser};
+
+/// Markup that's only included if a condition holds at compile time.
+///
+/// #if env == prod {
+///     script(src: analytics.js);
+/// } #elif env == dev {
+///     div#debug-overlay;
+/// } #else {
+///     ...
+/// }
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Conditional {
+    pub condition: Condition,
+    pub then: Body,
+    /// #else branch. #elif is represented as another conditional in here.
+    pub otherwise: Body,
+}
+
+impl Parser for Conditional {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        let (input, _) = terminated(tag("#if"), multispace1)(input)?;
+        Self::parse_branches(input)
+    }
+}
+
+impl Conditional {
+    /// Everything after #if or #elif
+    fn parse_branches(input: &str) -> nom::IResult<&str, Self> {
+        let (input, condition) = context("expected condition", cut(Condition::parse))(input)?;
+        let (input, then) = context("expected body of #if", cut(Body::parse_trim))(input)?;
+
+        let (input, otherwise) = opt(preceded(
+            multispace0,
+            alt((
+                preceded(
+                    terminated(tag("#elif"), multispace1),
+                    map(cut(Self::parse_branches), |c| vec![crate::Element::If(c)]),
+                ),
+                preceded(
+                    tag("#else"),
+                    context("expected body of #else", cut(Body::parse_trim)),
+                ),
+            )),
+        ))(input)?;
+
+        Ok((
+            input,
+            Conditional {
+                condition,
+                then,
+                otherwise: otherwise.unwrap_or_default(),
+            },
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// name
+    Defined(Ident),
+    /// !name
+    Undefined(Ident),
+    /// name == value
+    Equal(Ident, String),
+    /// name != value
+    NotEqual(Ident, String),
+}
+
+impl Default for Condition {
+    fn default() -> Self {
+        Condition::Defined(Ident::default())
+    }
+}
+
+impl Parser for Condition {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        fn value(input: &str) -> nom::IResult<&str, String> {
+            alt((String::parse, map(Ident::parse, |i| i.0)))(input)
+        }
+
+        fn operator<'a>(op: &'static str) -> impl FnMut(&'a str) -> nom::IResult<&'a str, &'a str> {
+            terminated(preceded(multispace0, tag(op)), multispace0)
+        }
+
+        alt((
+            map(
+                separated_pair(Ident::parse, operator("=="), value),
+                |(name, value)| Condition::Equal(name, value),
+            ),
+            map(
+                separated_pair(Ident::parse, operator("!="), value),
+                |(name, value)| Condition::NotEqual(name, value),
+            ),
+            map(preceded(char('!'), Ident::parse), Condition::Undefined),
+            map(Ident::parse, Condition::Defined),
+        ))(input)
+    }
+}
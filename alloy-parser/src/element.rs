@@ -1,4 +1,8 @@
-use crate::{Node, Parser};
+use crate::{
+    parser::{doc_comment, skip_comment},
+    Block, Component, Conditional, Extends, HtmlComment, Import, Include, MarkedText, Node, Parser,
+    Raw, StyleBlock,
+};
 use nom::{branch::alt, combinator::map};
 
 /// Represents an element in the DOM tree.
@@ -7,6 +11,26 @@ use nom::{branch::alt, combinator::map};
 pub enum Element {
     Node(Node),
     Text(String),
+    /// Text with whitespace markers, e.g. 'here'>
+    MarkedText(MarkedText),
+    /// Definition of a component. Removed from the tree by `expand`.
+    Component(Component),
+    /// Replaced by the content of another file by `expand`.
+    Include(Include),
+    /// Makes the components of another file available. Removed from the tree by `expand`.
+    Import(Import),
+    /// Renders a layout instead of the current file. Removed from the tree by `expand`.
+    Extends(Extends),
+    /// Section of a layout. Replaced by its content by `expand`.
+    Block(Block),
+    /// Replaced by one of its branches by `expand`.
+    If(Conditional),
+    /// Written to the output as <!-- ... -->
+    Comment(HtmlComment),
+    /// Written to the output without escaping.
+    Raw(Raw),
+    /// Css declarations, merged into the style attribute of the surrounding node.
+    Style(StyleBlock),
 }
 
 impl Default for Element {
@@ -18,8 +42,48 @@ impl Default for Element {
 impl Parser for Element {
     fn parse(input: &str) -> nom::IResult<&str, Self> {
         alt((
+            map(HtmlComment::parse, Element::Comment),
+            map(Raw::parse, Element::Raw),
+            map(Component::parse, Element::Component),
+            map(Include::parse, Element::Include),
+            map(Import::parse, Element::Import),
+            map(Extends::parse, Element::Extends),
+            map(Block::parse, Element::Block),
+            map(Conditional::parse, Element::If),
+            map(StyleBlock::parse, Element::Style),
             map(Node::parse, Element::Node),
+            map(MarkedText::parse, Element::MarkedText),
             map(String::parse, Element::Text),
         ))(input)
     }
+
+    fn parse_trim(input: &str) -> nom::IResult<&str, Self> {
+        let input = input.trim_start();
+
+        // /*! ... */ is an element of its own, other comments are cut out as usual
+        if input.starts_with("/*!") {
+            return Self::parse(input);
+        }
+        // /// docs belong to the next node or component
+        if let Some((doc, rest)) = doc_comment(input) {
+            let (rest, mut element) = Self::parse_trim(rest)?;
+            let docs = match element {
+                Element::Node(ref mut node) => Some(&mut node.doc),
+                Element::Component(ref mut component) => Some(&mut component.doc),
+                _ => None,
+            };
+            if let Some(docs) = docs {
+                *docs = Some(match docs.take() {
+                    Some(more) => format!("{doc}\n{more}"),
+                    None => doc.to_string(),
+                });
+            }
+            return Ok((rest, element));
+        }
+        if let Some(rest) = skip_comment(input) {
+            return Self::parse_trim(rest);
+        }
+
+        Self::parse(input)
+    }
 }
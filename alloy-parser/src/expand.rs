@@ -0,0 +1,1048 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::markdown::markdown_to_body;
+use crate::parser::{with_mode, Mode};
+use crate::table::{parse_csv, table_children, take_header};
+use crate::{
+    braces, parse, parse_strict, pragmas, Attribute, AttributeValue, Body, Component, Condition,
+    Element, Filter, Filters, HtmlComment, Ident, Interpolation, Node, Pragmas, Raw,
+};
+
+/// Variables visible to `${name}` interpolation.
+type Scope = HashMap<String, String>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpandError {
+    /// A component was used without an argument that has no default.
+    MissingArgument { component: String, argument: String },
+    /// A component was used with an argument it doesn't declare.
+    UnknownArgument { component: String, argument: String },
+    /// A component (indirectly) uses itself.
+    RecursiveComponent(String),
+    /// A file couldn't be read.
+    Io { path: PathBuf, error: String },
+    /// A file couldn't be parsed.
+    Parse { path: PathBuf, error: String },
+    /// A file (indirectly) includes or imports itself.
+    IncludeCycle(PathBuf),
+    /// The override of a block (indirectly) uses the block itself.
+    RecursiveBlock(String),
+    /// `extends` was used inside of a body, instead of on the top level of a file.
+    NestedExtends(String),
+    /// An interpolation uses a filter that isn't registered.
+    UnknownFilter(String),
+    /// A filter rejected its input.
+    Filter { filter: String, error: String },
+}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpandError::MissingArgument {
+                component,
+                argument,
+            } => write!(f, "component {component} is missing argument {argument}"),
+            ExpandError::UnknownArgument {
+                component,
+                argument,
+            } => write!(f, "component {component} has no parameter {argument}"),
+            ExpandError::RecursiveComponent(name) => {
+                write!(f, "component {name} is used recursively")
+            }
+            ExpandError::Io { path, error } => write!(f, "read {}: {error}", path.display()),
+            ExpandError::Parse { path, error } => write!(f, "parse {}: {error}", path.display()),
+            ExpandError::IncludeCycle(path) => {
+                write!(f, "{} includes itself", path.display())
+            }
+            ExpandError::RecursiveBlock(name) => write!(f, "block {name} is used recursively"),
+            ExpandError::NestedExtends(path) => {
+                write!(
+                    f,
+                    "extends \"{path}\" is only allowed on the top level of a file"
+                )
+            }
+            ExpandError::UnknownFilter(name) => write!(f, "unknown filter {name}"),
+            ExpandError::Filter { filter, error } => write!(f, "filter {filter}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ExpandError {}
+
+/// Content of a block, given by a file extending a layout.
+#[derive(Debug, Clone)]
+struct Override {
+    /// Paths inside of the block are relative to this file, not to the layout.
+    file: PathBuf,
+    body: Body,
+}
+
+/// A component, along with the file it's defined in.
+#[derive(Debug, Clone)]
+struct Definition {
+    /// Paths inside of the component are relative to this file, not to the caller.
+    file: PathBuf,
+    component: Component,
+}
+
+/// Content passed to a component by its caller.
+#[derive(Default, Debug)]
+struct Slots {
+    default: Body,
+    named: HashMap<String, Body>,
+}
+
+/// Resolves components in a parsed tree, so that only plain html nodes and text remain.
+#[derive(Default, Debug)]
+pub struct Expander {
+    components: HashMap<String, Definition>,
+    /// Slots of the components currently being expanded, innermost last.
+    slots: Vec<Slots>,
+    /// Names of the components currently being expanded, innermost last.
+    stack: Vec<String>,
+    /// Files currently being expanded, innermost last.
+    /// Paths of includes and imports are relative to the last one.
+    files: Vec<PathBuf>,
+    /// Blocks overridden by files extending a layout.
+    blocks: HashMap<String, Override>,
+    /// Names of the overridden blocks currently being expanded, innermost last.
+    overriding: Vec<String>,
+    /// Compile time constants, visible everywhere.
+    defines: Scope,
+    /// Filters usable in interpolations.
+    filters: Filters,
+    /// Pragmas of the file given to `expand_file`.
+    pragmas: Pragmas,
+    /// Parses all files in strict mode, not only those with the pragma.
+    strict: bool,
+}
+
+/// Expands all components in `body`.
+/// Includes and imports are resolved relative to the current directory.
+pub fn expand(body: Body) -> Result<Body, ExpandError> {
+    Expander::default().expand(body)
+}
+
+/// Reads, parses and expands the file at `path`.
+pub fn expand_file(path: impl AsRef<Path>) -> Result<Body, ExpandError> {
+    Expander::default().expand_file(path)
+}
+
+impl Expander {
+    /// Defines a constant, usable in conditions and as `${name}`.
+    pub fn define(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.defines.insert(name.into(), value.into());
+        self
+    }
+
+    /// Registers a filter usable as `${value | name}`. Replaces builtin filters of the same name.
+    pub fn register_filter(
+        &mut self,
+        name: impl Into<String>,
+        filter: impl Filter + 'static,
+    ) -> &mut Self {
+        self.filters.insert(name, filter);
+        self
+    }
+
+    pub fn expand(&mut self, body: Body) -> Result<Body, ExpandError> {
+        let scope = self.defines.clone();
+        self.expand_file_body(body, &scope)
+    }
+
+    pub fn expand_file(&mut self, path: impl AsRef<Path>) -> Result<Body, ExpandError> {
+        let (path, pragmas, body) = self.load(path.as_ref())?;
+        if self.files.is_empty() {
+            self.pragmas = pragmas;
+        }
+
+        self.files.push(path);
+        let result = self.expand(body);
+        self.files.pop();
+
+        result
+    }
+
+    /// Parses all files as if they had the strict pragma.
+    pub fn strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Pragmas of the file given to `expand_file`, e.g. the doctype to write.
+    pub fn pragmas(&self) -> &Pragmas {
+        &self.pragmas
+    }
+
+    fn expand_body(&mut self, body: Body, scope: &Scope) -> Result<Body, ExpandError> {
+        // definitions are visible in the whole body they are declared in, and everything nested below.
+        let outer = self.components.clone();
+        let result = self.expand_declarations(body, scope);
+        self.components = outer;
+
+        result
+    }
+
+    /// Expands the top level of a file, which might extend a layout.
+    fn expand_file_body(&mut self, body: Body, scope: &Scope) -> Result<Body, ExpandError> {
+        let layout = body.iter().find_map(|e| match e {
+            Element::Extends(extends) => Some(extends.0.clone()),
+            _ => None,
+        });
+        let Some(layout) = layout else {
+            return self.expand_body(body, scope);
+        };
+
+        let outer = self.components.clone();
+        let result = self.extend(&layout, body, scope);
+        self.components = outer;
+
+        result
+    }
+
+    fn expand_declarations(&mut self, body: Body, scope: &Scope) -> Result<Body, ExpandError> {
+        let mut elements = Vec::with_capacity(body.len());
+        for element in body {
+            match element {
+                Element::Component(c) => self.define_component(c),
+                Element::Import(import) => self.import(&import.0)?,
+                e => elements.push(e),
+            }
+        }
+
+        let mut out = Body::with_capacity(elements.len());
+        for element in elements {
+            self.expand_element(element, scope, &mut out)?;
+        }
+
+        Ok(out)
+    }
+
+    fn expand_element(
+        &mut self,
+        element: Element,
+        scope: &Scope,
+        out: &mut Body,
+    ) -> Result<(), ExpandError> {
+        let mut node = match element {
+            Element::Text(t) => {
+                out.extend(self.interpolate_text(&t, scope)?);
+                return Ok(());
+            }
+            Element::MarkedText(mut marked) => {
+                marked.body = self.expand_body(marked.body, scope)?;
+                out.push(Element::MarkedText(marked));
+                return Ok(());
+            }
+            // raw html is written as it is, it might as well contain javascript using ${}
+            Element::Raw(raw) => {
+                out.push(Element::Raw(raw));
+                return Ok(());
+            }
+            Element::Comment(comment) => {
+                let text = self.interpolate(&comment.0, scope)?;
+                out.push(Element::Comment(HtmlComment(text)));
+                return Ok(());
+            }
+            Element::Include(include) => {
+                out.extend(self.include(&include.0, scope)?);
+                return Ok(());
+            }
+            Element::Block(block) => {
+                out.extend(self.expand_block(block.name.0, block.body, scope)?);
+                return Ok(());
+            }
+            Element::If(conditional) => {
+                let body = match evaluate(&conditional.condition, scope) {
+                    true => conditional.then,
+                    false => conditional.otherwise,
+                };
+                out.extend(self.expand_body(body, scope)?);
+                return Ok(());
+            }
+            Element::Style(mut style) => {
+                for attr in style.0.iter_mut() {
+                    if let Some(ref mut value) = attr.value {
+                        self.interpolate_value(value, scope)?;
+                    }
+                }
+                out.push(Element::Style(style));
+                return Ok(());
+            }
+            Element::Extends(extends) => return Err(ExpandError::NestedExtends(extends.0)),
+            Element::Component(_) | Element::Import(_) => return Ok(()),
+            Element::Node(node) => node,
+        };
+
+        if let Some(ref mut attrs) = node.attributes {
+            for attr in attrs.0.iter_mut() {
+                if let Some(ref mut value) = attr.value {
+                    self.interpolate_value(value, scope)?;
+                }
+            }
+        }
+
+        if node.kind.0 == "slot" && !self.slots.is_empty() {
+            return self.fill_slot(node, scope, out);
+        }
+
+        if node.kind.0 == "table" {
+            if let Some(csv) = node.take_attribute("csv").and_then(|a| a.value) {
+                let rows = self.read_csv(&csv.to_string())?;
+                let header = take_header(&mut node);
+                node.body = self.expand_body(node.body, scope)?;
+                node.body.extend(table_children(rows, header));
+                out.push(Element::Node(node));
+                return Ok(());
+            }
+        }
+
+        // markdown(src: post.md);
+        // markdown "# Title"
+        // markdown.post "..." is wrapped in div.post
+        if node.kind.0 == "markdown" {
+            let source = match node.take_attribute("src").and_then(|a| a.value) {
+                Some(src) => self.read(&src.to_string())?,
+                None => text(&self.expand_body(std::mem::take(&mut node.body), scope)?),
+            };
+            let body = markdown_to_body(&source);
+            if node.ids_and_classes.is_empty() && node.attributes.is_none() {
+                out.extend(body);
+            } else {
+                node.kind = Ident(String::from("div"));
+                node.body = body;
+                out.push(Element::Node(node));
+            }
+            return Ok(());
+        }
+
+        if let Some(definition) = self.components.get(&node.kind.0).cloned() {
+            out.extend(self.instantiate(&definition, node, scope)?);
+            return Ok(());
+        }
+
+        node.body = self.expand_body(node.body, scope)?;
+        out.push(Element::Node(node));
+        Ok(())
+    }
+
+    fn interpolate_value(
+        &self,
+        value: &mut AttributeValue,
+        scope: &Scope,
+    ) -> Result<(), ExpandError> {
+        match value {
+            AttributeValue::String(s) => *s = self.interpolate(s, scope)?,
+            // disabled: ${d} is a boolean, once d is true or false
+            AttributeValue::Expression(s) => {
+                *value = AttributeValue::typed(self.interpolate(s, scope)?);
+            }
+            AttributeValue::List(items) => {
+                for item in items.iter_mut() {
+                    *item = self.interpolate(item, scope)?;
+                }
+            }
+            AttributeValue::Map(entries) => {
+                for entry in entries.iter_mut() {
+                    if let Some(ref mut value) = entry.value {
+                        self.interpolate_value(value, scope)?;
+                    }
+                }
+            }
+            AttributeValue::Bool(_) | AttributeValue::Number(_) => {}
+        }
+        Ok(())
+    }
+
+    /// slot;                   -> default content
+    /// slot(name: footer);     -> content of children marked with (slot: footer)
+    /// slot { p 'fallback' }   -> body is used, if the caller provides nothing
+    fn fill_slot(&mut self, node: Node, scope: &Scope, out: &mut Body) -> Result<(), ExpandError> {
+        let name = node.attributes.as_ref().and_then(|attrs| {
+            attrs
+                .0
+                .iter()
+                .find(|a| a.key.0 == "name")
+                .and_then(|a| a.value.as_ref())
+                .map(|v| v.to_string())
+        });
+
+        let slots = self.slots.last().expect("inside of component");
+        let content = match name {
+            Some(name) => slots.named.get(&name),
+            None => Some(&slots.default),
+        };
+
+        match content {
+            Some(content) if !content.is_empty() => out.extend(content.iter().cloned()),
+            _ => out.extend(self.expand_body(node.body, scope)?),
+        }
+
+        Ok(())
+    }
+
+    fn instantiate(
+        &mut self,
+        definition: &Definition,
+        node: Node,
+        scope: &Scope,
+    ) -> Result<Body, ExpandError> {
+        let component = &definition.component;
+        let name = &component.name.0;
+        if self.stack.contains(name) {
+            return Err(ExpandError::RecursiveComponent(name.clone()));
+        }
+
+        let mut args = Scope::new();
+        for Attribute { key, value } in node.attributes.map(|a| a.0).unwrap_or_default() {
+            if !component.params.0.iter().any(|p| p.key == key) {
+                return Err(ExpandError::UnknownArgument {
+                    component: name.clone(),
+                    argument: key.0,
+                });
+            }
+            args.insert(key.0, value.map(|v| v.to_string()).unwrap_or_default());
+        }
+        for param in component.params.0.iter() {
+            if args.contains_key(&param.key.0) {
+                continue;
+            }
+            let Some(ref default) = param.value else {
+                return Err(ExpandError::MissingArgument {
+                    component: name.clone(),
+                    argument: param.key.0.clone(),
+                });
+            };
+            args.insert(param.key.0.clone(), default.to_string());
+        }
+        let mut params = self.defines.clone();
+        params.extend(args);
+
+        // content of the slots belongs to the caller, so it's expanded in the callers scope.
+        let mut slots = Slots::default();
+        let mut named = Vec::new();
+        let mut default = Body::new();
+        for element in node.body {
+            match element {
+                Element::Node(mut n) => match n.take_attribute("slot").and_then(|a| a.value) {
+                    Some(slot) => named.push((slot.to_string(), n)),
+                    None => default.push(Element::Node(n)),
+                },
+                e => default.push(e),
+            }
+        }
+        slots.default = self.expand_body(default, scope)?;
+        for (slot, n) in named {
+            let content = self.expand_body(vec![Element::Node(n)], scope)?;
+            slots.named.entry(slot).or_default().extend(content);
+        }
+
+        self.slots.push(slots);
+        self.stack.push(name.clone());
+        self.files.push(definition.file.clone());
+        let result = self.expand_body(component.body.clone(), &params);
+        self.files.pop();
+        self.stack.pop();
+        self.slots.pop();
+        let mut body = result?;
+
+        // div.wide#main> -> ids, classes and whitespace control end up on the root of the component
+        if let Some(Element::Node(root)) = body.iter_mut().find(|e| matches!(e, Element::Node(_))) {
+            root.ids_and_classes.extend(node.ids_and_classes);
+            root.whitespace.trim_inside |= node.whitespace.trim_inside;
+            root.whitespace.trim_outside |= node.whitespace.trim_outside;
+        }
+
+        Ok(body)
+    }
+}
+
+impl Expander {
+    /// Makes `component` usable in the current body. Its paths are relative to the current file.
+    fn define_component(&mut self, component: Component) {
+        let file = self.files.last().cloned().unwrap_or_default();
+        self.components
+            .insert(component.name.0.clone(), Definition { file, component });
+    }
+
+    /// `path` relative to the file currently being expanded.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        match self.files.last().and_then(|f| f.parent()) {
+            Some(dir) => dir.join(path),
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// table(csv: prices.csv, header: true);
+    fn read_csv(&self, path: &str) -> Result<Vec<Vec<String>>, ExpandError> {
+        self.read(path).map(|content| parse_csv(&content))
+    }
+
+    /// Reads the file at `path`, relative to the file currently being expanded.
+    fn read(&self, path: &str) -> Result<String, ExpandError> {
+        let path = self.resolve(Path::new(path));
+        std::fs::read_to_string(&path).map_err(|error| ExpandError::Io {
+            path,
+            error: error.to_string(),
+        })
+    }
+
+    /// Reads and parses the file at `path`, relative to the file currently being expanded.
+    /// Returns the canonical path of the file along with its pragmas and content.
+    fn load(&self, path: &Path) -> Result<(PathBuf, Pragmas, Body), ExpandError> {
+        let path = self.resolve(path);
+
+        let io_error = |error: std::io::Error| ExpandError::Io {
+            path: path.clone(),
+            error: error.to_string(),
+        };
+        let path = path.canonicalize().map_err(io_error)?;
+        if self.files.contains(&path) {
+            return Err(ExpandError::IncludeCycle(path));
+        }
+
+        let mut content = std::fs::read_to_string(&path).map_err(io_error)?;
+        // #!alloy indent is handled by parse
+        let indented = path.extension().is_some_and(|e| e == "ialloy");
+        let pragmas = pragmas(&content);
+        if indented && !pragmas.indented {
+            // comments nest since edition 2
+            let mode = Mode {
+                strict: self.strict,
+                edition: pragmas.edition,
+            };
+            content = with_mode(mode, || braces(&content));
+        }
+        // the pragmas are applied by parse itself
+        let parsed = match self.strict {
+            true => parse_strict(&content),
+            false => parse(&content),
+        };
+        let body = match parsed {
+            Ok((_, body)) => body,
+            Err(error) => {
+                return Err(ExpandError::Parse {
+                    path,
+                    error: error.to_string(),
+                })
+            }
+        };
+
+        Ok((path, pragmas, body))
+    }
+
+    /// Expands another file in the current scope.
+    fn include(&mut self, path: &str, scope: &Scope) -> Result<Body, ExpandError> {
+        let (path, pragmas, body) = self.load(Path::new(path))?;
+        // e.g. a page gets the doctype of its layout
+        if self.pragmas.doctype.is_none() {
+            self.pragmas.doctype = pragmas.doctype;
+        }
+
+        self.files.push(path);
+        let result = self.expand_file_body(body, scope);
+        self.files.pop();
+
+        result
+    }
+
+    /// Registers the components defined on the top level of another file.
+    /// Everything else in that file is ignored.
+    fn import(&mut self, path: &str) -> Result<(), ExpandError> {
+        let (path, _, body) = self.load(Path::new(path))?;
+
+        self.files.push(path);
+        let result = body.into_iter().try_for_each(|element| match element {
+            Element::Component(c) => {
+                self.define_component(c);
+                Ok(())
+            }
+            Element::Import(import) => self.import(&import.0),
+            _ => Ok(()),
+        });
+        self.files.pop();
+
+        result
+    }
+}
+
+impl Expander {
+    /// Expands the layout at `path`, using the blocks of `body` in place of its own.
+    fn extend(&mut self, path: &str, body: Body, scope: &Scope) -> Result<Body, ExpandError> {
+        // the overrides only apply to this layout, not to the next file expanded
+        let outer = self.blocks.clone();
+        for element in body {
+            match element {
+                Element::Component(c) => self.define_component(c),
+                Element::Import(import) => self.import(&import.0)?,
+                // the layout might extend another layout. The block closest to the page wins.
+                Element::Block(block) => {
+                    let file = self.files.last().cloned().unwrap_or_default();
+                    self.blocks.entry(block.name.0).or_insert(Override {
+                        file,
+                        body: block.body,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let result = self.include(path, scope);
+        self.blocks = outer;
+
+        result
+    }
+
+    /// Expands the override of the block `name`, or its own `body` if there is none.
+    fn expand_block(
+        &mut self,
+        name: String,
+        body: Body,
+        scope: &Scope,
+    ) -> Result<Body, ExpandError> {
+        let Some(Override { file, body }) = self.blocks.get(&name).cloned() else {
+            return self.expand_body(body, scope);
+        };
+        if self.overriding.contains(&name) {
+            return Err(ExpandError::RecursiveBlock(name));
+        }
+
+        self.files.push(file);
+        self.overriding.push(name);
+        let result = self.expand_body(body, scope);
+        self.overriding.pop();
+        self.files.pop();
+
+        result
+    }
+}
+
+/// Text of a body, e.g. the source of markdown "..."
+///
+/// Every text is a line of its own.
+/// Html of filters, e.g. ${post | markdown}, is part of the line it's used in.
+fn text(body: &Body) -> String {
+    let mut text = String::new();
+    let mut after_text = false;
+    for element in body {
+        match element {
+            Element::Text(t) => {
+                if after_text {
+                    text.push('\n');
+                }
+                text.push_str(t);
+                after_text = true;
+            }
+            Element::Raw(raw) => {
+                text.push_str(&raw.0);
+                after_text = false;
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+fn evaluate(condition: &Condition, scope: &Scope) -> bool {
+    match condition {
+        Condition::Defined(name) => scope.contains_key(&name.0),
+        Condition::Undefined(name) => !scope.contains_key(&name.0),
+        Condition::Equal(name, value) => scope.get(&name.0) == Some(value),
+        Condition::NotEqual(name, value) => scope.get(&name.0) != Some(value),
+    }
+}
+
+impl Expander {
+    /// Replaces every `${name}` with its value in `scope`, passed through its filters.
+    /// Unknown names are left as is, they might as well be part of some inline javascript.
+    fn interpolate(&self, s: &str, scope: &Scope) -> Result<String, ExpandError> {
+        let parts = self.interpolate_parts(s, scope)?;
+        Ok(parts.into_iter().map(|(part, _)| part).collect())
+    }
+
+    /// Interpolates text. Values that filters turned into html, e.g. ${post | markdown},
+    /// become raw html, so that they aren't escaped.
+    fn interpolate_text(&self, s: &str, scope: &Scope) -> Result<Body, ExpandError> {
+        let mut body = Body::new();
+        for (part, html) in self.interpolate_parts(s, scope)? {
+            match (html, body.last_mut()) {
+                (true, _) => body.push(Element::Raw(Raw(part))),
+                (false, _) if part.is_empty() => {}
+                (false, Some(Element::Text(t))) => t.push_str(&part),
+                (false, _) => body.push(Element::Text(part)),
+            }
+        }
+        if body.is_empty() {
+            body.push(Element::Text(String::new()));
+        }
+        Ok(body)
+    }
+
+    /// Parts of `s` after interpolation, along with whether they are html.
+    fn interpolate_parts(
+        &self,
+        s: &str,
+        scope: &Scope,
+    ) -> Result<Vec<(String, bool)>, ExpandError> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+
+        while let Some(start) = rest.find("${") {
+            parts.push((rest[..start].to_string(), false));
+            rest = &rest[start..];
+
+            let Some(end) = rest.find('}') else {
+                break;
+            };
+
+            let interpolation = Interpolation::parse_complete(&rest[2..end]);
+            let value = interpolation
+                .as_ref()
+                .and_then(|i| Some((i, scope.get(&i.name.0)?)));
+
+            match value {
+                Some((interpolation, value)) => {
+                    parts.push(self.apply_filters(value, interpolation)?)
+                }
+                None => parts.push((rest[..=end].to_string(), false)),
+            }
+            rest = &rest[end + 1..];
+        }
+
+        parts.push((rest.to_string(), false));
+        Ok(parts)
+    }
+
+    /// The filtered value, and whether it's html. Once html, filters can't turn it back into text.
+    fn apply_filters(
+        &self,
+        value: &str,
+        interpolation: &Interpolation,
+    ) -> Result<(String, bool), ExpandError> {
+        let mut value = value.to_string();
+        let mut html = false;
+
+        for call in interpolation.filters.iter() {
+            let name = &call.name.0;
+            let Some(filter) = self.filters.get(name) else {
+                return Err(ExpandError::UnknownFilter(name.clone()));
+            };
+            value = filter
+                .apply(&value, &call.args)
+                .map_err(|error| ExpandError::Filter {
+                    filter: name.clone(),
+                    error,
+                })?;
+            html |= filter.html();
+        }
+
+        Ok((value, html))
+    }
+}
+
+#[cfg(test)]
+mod expand_tests {
+    use super::*;
+    use crate::{parse, Parser};
+
+    fn expanded(input: &str) -> Result<Body, ExpandError> {
+        let (rest, body) = parse(input).expect("parse input");
+        assert_eq!(rest, "", "nothing remains");
+        expand(body)
+    }
+
+    #[test]
+    fn interpolation() {
+        let scope = Scope::from([(String::from("title"), String::from("Hello"))]);
+        let mut expander = Expander::default();
+        expander.register_filter("twice", |v: &str, _: &[String]| Ok(v.repeat(2)));
+        let interpolate = |s| expander.interpolate(s, &scope);
+
+        assert_eq!(
+            interpolate("${title} world"),
+            Ok(String::from("Hello world"))
+        );
+        assert_eq!(interpolate("${ title }!"), Ok(String::from("Hello!")));
+        assert_eq!(interpolate("`${x}`"), Ok(String::from("`${x}`")));
+        assert_eq!(interpolate("${title"), Ok(String::from("${title")));
+        assert_eq!(
+            interpolate("${x | upper}"),
+            Ok(String::from("${x | upper}"))
+        );
+        assert_eq!(
+            interpolate("${title | upper | twice}"),
+            Ok(String::from("HELLOHELLO"))
+        );
+        assert_eq!(
+            interpolate("${title | nope}"),
+            Err(ExpandError::UnknownFilter(String::from("nope")))
+        );
+    }
+
+    #[test]
+    fn html_filters() {
+        let (_, body) = parse("p 'Intro: ${post | markdown}'").expect("parse input");
+        let mut expander = Expander::default();
+        expander.define("post", "*hi*");
+
+        assert_eq!(
+            expander.expand(body),
+            Ok(Body::from_s("p { 'Intro: ' raw '<p><em>hi</em></p>\n' }"))
+        );
+
+        // the html stays part of the markdown source
+        let (_, body) = parse("markdown '# ${title | upper}: ${post | markdown}'").expect("parse");
+        expander.define("title", "news");
+        assert_eq!(
+            expander.expand(body),
+            Ok(Body::from_s(
+                "h1 { 'NEWS: ' raw '<p>' raw '<em>' 'hi' raw '</em>' raw '</p>' }"
+            ))
+        );
+    }
+
+    #[test]
+    fn component_with_slots() {
+        let input = "
+            component Card(title, href: '#') {
+                div.card {
+                    a(href: '${href}') '${title}'
+                    slot;
+                    slot(name: footer) { 'no footer' }
+                }
+            }
+
+            Card.wide(title: 'x', href: y) { p 'body' }
+            Card(title: 'z') { p(slot: footer) 'bottom' }
+        ";
+
+        let expected = Body::from_s(
+            "{
+                div.card.wide {
+                    a(href: 'y') 'x'
+                    p 'body'
+                    'no footer'
+                }
+                div.card {
+                    a(href: '#') 'z'
+                    p 'bottom'
+                }
+            }",
+        );
+
+        assert_eq!(expanded(input), Ok(expected));
+    }
+
+    #[test]
+    fn typed_interpolation() {
+        let input = "
+            component Button(d: false) { button(disabled: ${d}, title: '${d}', tabindex: ${i}) 'x' }
+            Button(d: true);
+            Button;
+        ";
+        let mut expander = Expander::default();
+        expander.define("i", "-1");
+        let (_, body) = parse(input).expect("parse input");
+        let expected = Body::from_s(
+            "{
+                button(disabled: true, title: 'true', tabindex: -1) 'x'
+                button(disabled: false, title: 'false', tabindex: -1) 'x'
+            }",
+        );
+
+        assert_eq!(expander.expand(body), Ok(expected));
+    }
+
+    #[test]
+    fn component_errors() {
+        let card = "component Card(title) { h2 '${title}' }";
+
+        assert_eq!(
+            expanded(&format!("{card} Card;")),
+            Err(ExpandError::MissingArgument {
+                component: String::from("Card"),
+                argument: String::from("title"),
+            })
+        );
+        assert_eq!(
+            expanded(&format!("{card} Card(title: a, href: b);")),
+            Err(ExpandError::UnknownArgument {
+                component: String::from("Card"),
+                argument: String::from("href"),
+            })
+        );
+        assert_eq!(
+            expanded("component A { div B; } component B { A; } A;"),
+            Err(ExpandError::RecursiveComponent(String::from("A")))
+        );
+    }
+
+    #[test]
+    fn include_and_import() {
+        let expected = Body::from_s(
+            "{
+                html {
+                    head {
+                        meta(charset: UTF-8);
+                        link(
+                            rel: stylesheet,
+                            href: \"https://stackpath.bootstrapcdn.com/bootstrap/4.3.1/css/bootstrap.min.css\"
+                        );
+                    }
+                    body {
+                        h1.title 'Welcome to my webpage!'
+                        p.byline 'Written by Alloy'
+                    }
+                }
+            }",
+        );
+
+        assert_eq!(expand_file("./samples/include.alloy"), Ok(expected));
+    }
+
+    #[test]
+    fn include_cycle() {
+        let result = expand_file("./samples/partials/cycle-a.alloy");
+        let Err(ExpandError::IncludeCycle(path)) = result else {
+            panic!("expected include cycle, got {result:#?}");
+        };
+        assert!(path.ends_with("cycle-a.alloy"));
+    }
+
+    #[test]
+    fn layouts() {
+        let expected = Body::from_s(
+            "{
+                html {
+                    head {
+                        meta(charset: UTF-8);
+                        title 'My Post'
+                    }
+                    body {
+                        article {
+                            h1 'Hello'
+                            p.byline 'Written by Alloy'
+                        }
+                        footer 'post footer'
+                    }
+                }
+            }",
+        );
+
+        assert_eq!(expand_file("./samples/layout.alloy"), Ok(expected));
+    }
+
+    #[test]
+    fn reused_expander() {
+        let mut expander = Expander::default();
+        expander
+            .expand_file("./samples/layout.alloy")
+            .expect("expand page");
+
+        assert_eq!(
+            expander.expand_file("./samples/layouts/base.alloy"),
+            expand_file("./samples/layouts/base.alloy")
+        );
+    }
+
+    #[test]
+    fn layout_errors() {
+        assert_eq!(
+            expanded(
+                "extends \"samples/layouts/base.alloy\" block content { div { block content; } }"
+            ),
+            Err(ExpandError::RecursiveBlock(String::from("content")))
+        );
+        assert_eq!(
+            expanded("div { extends \"samples/layouts/base.alloy\" }"),
+            Err(ExpandError::NestedExtends(String::from(
+                "samples/layouts/base.alloy"
+            )))
+        );
+    }
+
+    #[test]
+    fn blocks_without_layout() {
+        assert_eq!(
+            expanded("div { block content { p 'default' } }"),
+            Ok(Body::from_s("div p 'default'"))
+        );
+    }
+
+    #[test]
+    fn conditionals() {
+        let input = "
+            #if env == prod {
+                script(src: analytics.js);
+            } #elif env != \"test\" {
+                div#debug '${env}'
+            } #else {
+                'testing'
+            }
+            #if !env 'no env'
+        ";
+        let (_, body) = parse(input).expect("parse input");
+
+        let mut expander = Expander::default();
+        expander.define("env", "prod");
+        assert_eq!(
+            expander.expand(body.clone()),
+            Ok(Body::from_s("script(src: analytics.js);"))
+        );
+
+        let mut expander = Expander::default();
+        expander.define("env", "dev");
+        assert_eq!(
+            expander.expand(body.clone()),
+            Ok(Body::from_s("div#debug 'dev'"))
+        );
+
+        let mut expander = Expander::default();
+        expander.define("env", "test");
+        assert_eq!(expander.expand(body.clone()), Ok(Body::from_s("'testing'")));
+
+        assert_eq!(
+            expand(body),
+            Ok(Body::from_s("{ div#debug '${env}' 'no env' }"))
+        );
+    }
+
+    #[test]
+    fn csv_table() {
+        let expected = Body::from_s(
+            "table.prices {
+                caption 'Prices'
+                thead tr { th 'Plan' th 'Price' }
+                tbody {
+                    tr { td 'Basic' td '5 €' }
+                    tr { td 'Pro' td '10 €' }
+                }
+            }",
+        );
+
+        assert_eq!(expand_file("./samples/prices.alloy"), Ok(expected));
+    }
+
+    #[test]
+    fn markdown() {
+        let mut expander = Expander::default();
+        expander.define("name", "you");
+        let expected = Body::from_s(
+            "article {
+                h1#hello 'Hello'
+                p { 'Written in ' em<> 'markdown' '.' }
+                p 'Thanks for reading, you!'
+            }",
+        );
+
+        assert_eq!(expander.expand_file("./samples/blog.alloy"), Ok(expected));
+
+        let (_, body) = parse("markdown.post#intro(lang: en) 'hi'").expect("parse input");
+        assert_eq!(
+            expander.expand(body),
+            Ok(Body::from_s("div.post#intro(lang: en) { p 'hi' }"))
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use nom::{
+    branch::alt,
+    character::complete::{char, multispace0},
+    combinator::{map, opt},
+    multi::{many0, separated_list0},
+    sequence::{delimited, preceded, terminated},
+};
+
+use crate::{keywords::*, Ident, Parser};
+
+/// Transforms a value inside of an interpolation.
+///
+/// ${title | upper}
+/// ${date | format("%d.%m.%Y")}
+///
+/// Implemented for all functions of the same signature,
+/// so that a closure may be registered using `Expander::register_filter`.
+pub trait Filter {
+    /// Errors are reported to the user as they are.
+    fn apply(&self, value: &str, args: &[String]) -> Result<String, String>;
+
+    /// Whether the output is html. Html inside of text is written without escaping.
+    fn html(&self) -> bool {
+        false
+    }
+}
+
+impl<F> Filter for F
+where
+    F: Fn(&str, &[String]) -> Result<String, String>,
+{
+    fn apply(&self, value: &str, args: &[String]) -> Result<String, String> {
+        self(value, args)
+    }
+}
+
+/// Filters by name. Comes with the builtin filters.
+pub struct Filters(HashMap<String, Box<dyn Filter>>);
+
+impl Filters {
+    pub fn insert(&mut self, name: impl Into<String>, filter: impl Filter + 'static) {
+        self.0.insert(name.into(), Box::new(filter));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Filter> {
+        self.0.get(name).map(|f| f.as_ref())
+    }
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        let mut filters = Filters(HashMap::new());
+        filters.insert("upper", |v: &str, _: &[String]| Ok(v.to_uppercase()));
+        filters.insert("lower", |v: &str, _: &[String]| Ok(v.to_lowercase()));
+        filters.insert("trim", |v: &str, _: &[String]| Ok(v.trim().to_string()));
+        filters.insert("urlencode", |v: &str, _: &[String]| Ok(urlencode(v)));
+        filters.insert("markdown", Markdown);
+        filters.insert("format", format_date);
+        filters
+    }
+}
+
+impl fmt::Debug for Filters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// Content of ${ ... }
+/// title | format("%Y") | upper
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Interpolation {
+    pub name: Ident,
+    pub filters: Vec<FilterCall>,
+}
+
+impl Parser for Interpolation {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        let (input, name) = Ident::parse(input)?;
+        let (input, filters) = many0(preceded(
+            preceded(multispace0, char('|')),
+            FilterCall::parse_trim,
+        ))(input)?;
+
+        Ok((input, Interpolation { name, filters }))
+    }
+}
+
+impl Interpolation {
+    /// Parses the whole input, ignoring surrounding whitespace.
+    pub(crate) fn parse_complete(input: &str) -> Option<Self> {
+        match terminated(Interpolation::parse_trim, multispace0)(input) {
+            Ok(("", interpolation)) => Some(interpolation),
+            _ => None,
+        }
+    }
+}
+
+/// format("%Y-%m-%d")
+/// upper
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct FilterCall {
+    pub name: Ident,
+    pub args: Vec<String>,
+}
+
+impl Parser for FilterCall {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        fn arg(input: &str) -> nom::IResult<&str, String> {
+            delimited(
+                multispace0,
+                alt((String::parse, map(Ident::parse, |i| i.0))),
+                multispace0,
+            )(input)
+        }
+
+        let (input, name) = Ident::parse(input)?;
+        let (input, args) = opt(delimited(
+            KeywordParenOpen::parse,
+            separated_list0(KeywordComma::parse, arg),
+            KeywordParenClose::parse,
+        ))(input)?;
+
+        Ok((
+            input,
+            FilterCall {
+                name,
+                args: args.unwrap_or_default(),
+            },
+        ))
+    }
+}
+
+/// Percent-encodes everything but the unreserved characters of RFC 3986.
+fn urlencode(value: &str) -> String {
+    let mut s = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                s.push(b as char)
+            }
+            b => s.push_str(&format!("%{b:02X}")),
+        }
+    }
+    s
+}
+
+/// Renders CommonMark to html.
+struct Markdown;
+
+impl Filter for Markdown {
+    fn apply(&self, value: &str, _: &[String]) -> Result<String, String> {
+        let parser = pulldown_cmark::Parser::new(value);
+        let mut html = String::new();
+        pulldown_cmark::html::push_html(&mut html, parser);
+        Ok(html)
+    }
+
+    fn html(&self) -> bool {
+        true
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// format("%d. %B %Y")
+///
+/// Formats a date given as `YYYY-MM-DD`, optionally followed by `THH:MM:SS` (or a space instead of T).
+/// Supports %Y %m %d %H %M %S %B %b and %%.
+fn format_date(value: &str, args: &[String]) -> Result<String, String> {
+    let [pattern] = args else {
+        return Err(String::from("format expects exactly one argument"));
+    };
+
+    let invalid = || format!("expected a date like 2022-11-13, got {value}");
+    let number = |s: Option<&str>| -> Result<u32, String> {
+        s.and_then(|s| s.parse().ok()).ok_or_else(invalid)
+    };
+
+    let value = value.trim();
+    let (date, time) = match value.split_once(['T', ' ']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (value, None),
+    };
+
+    let mut date = date.splitn(3, '-');
+    let year = number(date.next())?;
+    let month = number(date.next())?;
+    let day = number(date.next())?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+
+    let (hour, minute, second) = match time {
+        Some(time) => {
+            let mut time = time.splitn(3, ':');
+            let hour = number(time.next())?;
+            let minute = number(time.next())?;
+            // seconds may be omitted, and might carry fractions or a timezone
+            let second = time
+                .next()
+                .map(|s| s.get(..2).unwrap_or(s))
+                .map_or(Ok(0), |s| number(Some(s)))?;
+            (hour, minute, second)
+        }
+        None => (0, 0, 0),
+    };
+
+    let month_name = MONTHS[month as usize - 1];
+
+    let mut s = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            s.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => s.push_str(&format!("{year:04}")),
+            Some('m') => s.push_str(&format!("{month:02}")),
+            Some('d') => s.push_str(&format!("{day:02}")),
+            Some('H') => s.push_str(&format!("{hour:02}")),
+            Some('M') => s.push_str(&format!("{minute:02}")),
+            Some('S') => s.push_str(&format!("{second:02}")),
+            Some('B') => s.push_str(month_name),
+            Some('b') => s.push_str(&month_name[..3]),
+            Some('%') => s.push('%'),
+            Some(c) => return Err(format!("unknown format specifier %{c}")),
+            None => return Err(String::from("format ends with %")),
+        }
+    }
+
+    Ok(s)
+}
+
+#[cfg(test)]
+mod filter_tests {
+    use super::*;
+
+    #[test]
+    fn interpolation() {
+        let i = Interpolation::parse_complete(" date | format(\"%Y\") |upper ");
+        let expected = Interpolation {
+            name: Ident::from_s("date"),
+            filters: vec![
+                FilterCall {
+                    name: Ident::from_s("format"),
+                    args: vec![String::from("%Y")],
+                },
+                FilterCall {
+                    name: Ident::from_s("upper"),
+                    args: Vec::new(),
+                },
+            ],
+        };
+
+        assert_eq!(i, Some(expected));
+        assert_eq!(Interpolation::parse_complete("a + b"), None);
+    }
+
+    #[test]
+    fn builtins() {
+        let filters = Filters::default();
+        let apply = |name: &str, value: &str, args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+            filters
+                .get(name)
+                .expect("builtin filter")
+                .apply(value, &args)
+        };
+
+        assert_eq!(apply("upper", "hello", &[]), Ok(String::from("HELLO")));
+        assert_eq!(
+            apply("urlencode", "a b&c/ü", &[]),
+            Ok(String::from("a%20b%26c%2F%C3%BC"))
+        );
+        assert_eq!(
+            apply("markdown", "*hi*", &[]),
+            Ok(String::from("<p><em>hi</em></p>\n"))
+        );
+        assert_eq!(
+            apply("format", "2022-11-13T08:05:09Z", &["%d. %B %Y %H:%M:%S"]),
+            Ok(String::from("13. November 2022 08:05:09"))
+        );
+        assert!(apply("format", "13.11.2022", &["%Y"]).is_err());
+        assert!(apply("format", "2022-01-01T10:10:0é", &["%S"]).is_err());
+    }
+}
@@ -9,14 +9,60 @@ pub struct Ident(pub String);
 
 impl Parser for Ident {
     fn parse(input: &str) -> nom::IResult<&str, Self> {
-        let (rest, ident) = take_while1(
-            |c| matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '$' | '%' | '°'),
-        )(input)?;
+        let (rest, ident) = take_while1(is_ident_char)(input)?;
 
         Ok((rest, Ident(ident.to_string())))
     }
 }
 
+impl Ident {
+    /// Whether this is a valid html tag name.
+    ///
+    /// Either ascii letters and digits, like h1, or the name of a custom element.
+    /// Custom elements start with a lowercase letter and need a hyphen, e.g. my-element or mein-knöpfchen.
+    /// The few names reserved for svg and mathml, like font-face or annotation-xml, are accepted as well.
+    pub fn is_tag_name(&self) -> bool {
+        let name = self.0.as_str();
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return false;
+        }
+
+        if !name.contains('-') {
+            return name.chars().all(|c| c.is_ascii_alphanumeric());
+        }
+
+        name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name
+                .chars()
+                .all(|c| !c.is_ascii_uppercase() && (c == '.' || is_pcen_char(c)))
+    }
+}
+
+pub(crate) fn is_ident_char(c: char) -> bool {
+    matches!(c, 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '$' | '%' | '°') || is_pcen_char(c)
+}
+
+/// PCENChar of the html spec, without the . since it starts a class.
+/// https://html.spec.whatwg.org/multipage/custom-elements.html#valid-custom-element-name
+fn is_pcen_char(c: char) -> bool {
+    matches!(c,
+        '-' | '0'..='9' | '_' | 'a'..='z'
+        | '\u{B7}'
+        | '\u{C0}'..='\u{D6}'
+        | '\u{D8}'..='\u{F6}'
+        | '\u{F8}'..='\u{37D}'
+        | '\u{37F}'..='\u{1FFF}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{203F}'..='\u{2040}'
+        | '\u{2070}'..='\u{218F}'
+        | '\u{2C00}'..='\u{2FEF}'
+        | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}'
+        | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
 impl Deref for Ident {
     type Target = String;
     fn deref(&self) -> &Self::Target {
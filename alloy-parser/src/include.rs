@@ -0,0 +1,57 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::multispace0,
+    combinator::{map, opt},
+    sequence::{delimited, preceded},
+};
+
+use crate::{keywords::*, Parser};
+
+/// include "partials/header.alloy";
+///
+/// Inserts the content of another file in place.
+/// Paths are relative to the including file.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Include(pub String);
+
+impl Parser for Include {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        map(directive("include"), Include)(input)
+    }
+}
+
+/// import "components.alloy";
+///
+/// Makes the components defined in another file available, without inserting any content.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Import(pub String);
+
+impl Parser for Import {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        map(directive("import"), Import)(input)
+    }
+}
+
+/// extends "layouts/base.alloy"
+///
+/// Renders another file instead, with its blocks replaced by the blocks of this file.
+/// Everything else on the top level of this file, except for components and imports, is ignored.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Extends(pub String);
+
+impl Parser for Extends {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        map(directive("extends"), Extends)(input)
+    }
+}
+
+/// <name> <string> ;?
+fn directive(name: &'static str) -> impl Fn(&str) -> nom::IResult<&str, String> {
+    move |input| {
+        delimited(
+            preceded(tag(name), multispace0),
+            String::parse,
+            opt(KeywordNone::parse_trim),
+        )(input)
+    }
+}
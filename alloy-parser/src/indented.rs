@@ -0,0 +1,204 @@
+use crate::{parse, parser::comment_end, scan::Scanner};
+
+/// Brace-less dialect, where children are indented below their parent.
+/// Used for files ending in .ialloy
+///
+/// div.center
+///     h1 'Hello'
+///     img(src: logo.png)
+///
+/// is the same as
+///
+/// div.center {
+///     h1 'Hello'
+///     img(src: logo.png);
+/// }
+///
+/// Strings and comments may span multiple lines.
+pub fn braces(input: &str) -> String {
+    let lines = logical_lines(input);
+    let structural: Vec<usize> = (0..lines.len()).filter(|&i| !is_blank(lines[i])).collect();
+
+    let mut out = String::with_capacity(input.len());
+    // indentation of the lines whose block is still open
+    let mut open: Vec<usize> = Vec::new();
+    let mut next = structural.iter().peekable();
+
+    // comments go after the braces closed by the next line, /// docs belong to that line
+    let mut comments = String::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if is_blank(line) {
+            comments.push_str(line);
+            comments.push('\n');
+            continue;
+        }
+        next.next();
+
+        let indent = indentation(line);
+        while open.last().is_some_and(|&o| o >= indent) {
+            open.pop();
+            out.push_str("}\n");
+        }
+        out.push_str(&comments);
+        comments.clear();
+
+        out.push_str(line);
+        // braces go on a line of their own, a line might end with a comment
+        let has_children = next
+            .peek()
+            .is_some_and(|&&n| n > i && indentation(lines[n]) > indent);
+        if has_children {
+            out.push_str("\n{");
+            open.push(indent);
+        } else if needs_terminator(line) {
+            out.push_str("\n;");
+        }
+        out.push('\n');
+    }
+
+    for _ in open {
+        out.push_str("}\n");
+    }
+    out.push_str(&comments);
+
+    out
+}
+
+/// Lines, except for line breaks inside of strings, block comments and open ( or [,
+/// e.g. an attribute list spanning multiple lines.
+fn logical_lines(input: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut scanner = Scanner::default();
+    let mut rest = input;
+
+    while let Some(c) = rest.chars().next() {
+        let mut len = c.len_utf8();
+        let quoted = scanner.in_quote();
+        // inside of brackets, e.g. (href: https://example.com), comments follow whitespace
+        let after_whitespace = input[..input.len() - rest.len()].ends_with(char::is_whitespace);
+        let comment = !quoted && (!scanner.in_brackets() || after_whitespace);
+        if comment && rest.starts_with("//") {
+            len = rest.find('\n').unwrap_or(rest.len());
+        } else if comment && rest.starts_with("/*") {
+            len = comment_end(&rest[2..]).map_or(rest.len(), |i| i + 4);
+        } else {
+            if !quoted && !scanner.in_brackets() && c == '\n' {
+                let end = input.len() - rest.len();
+                lines.push(&input[start..end]);
+                start = end + 1;
+            }
+            scanner.next(c);
+        }
+        rest = &rest[len..];
+    }
+
+    if start < input.len() {
+        lines.push(&input[start..]);
+    }
+
+    lines
+}
+
+/// Empty or just a comment. /*! ... */ is an element, though.
+fn is_blank(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty()
+        || line.starts_with("//")
+        || (line.starts_with("/*") && !line.starts_with("/*!") && line.ends_with("*/"))
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// img(src: logo.png) needs a ; to be complete, p 'text' doesn't.
+fn needs_terminator(line: &str) -> bool {
+    let complete = |s: &str| matches!(parse(s), Ok(("", _)));
+    !complete(line) && complete(&format!("{line}\n;"))
+}
+
+#[cfg(test)]
+mod indented_tests {
+    use super::*;
+
+    #[test]
+    fn indentation() {
+        let indented = "
+html
+    // comment
+    head
+        title 'Hello'
+        meta(charset: utf-8) // comment
+    body.center
+        #if env == prod
+            p \"multiple
+lines\"
+        #else
+            p 'dev'
+        div p 'hello'
+        br
+";
+        let expected = "
+html {
+    head {
+        title 'Hello'
+        meta(charset: utf-8);
+    }
+    body.center {
+        #if env == prod {
+            p \"multiple
+lines\"
+        } #else {
+            p 'dev'
+        }
+        div p 'hello'
+        br;
+    }
+}
+";
+
+        let (_, expected) = parse(expected).expect("parse braces");
+        let (_, got) = parse(&braces(indented)).expect("parse indented");
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn multi_line_brackets() {
+        let indented = "
+head
+    link(
+        rel: stylesheet, // comment
+        href: https://example.com/style.css,
+        title: \"Print (A4)\"
+    )
+    title 'Hello'
+ul ['a',
+    'b']
+";
+        let expected = "
+head {
+    link(rel: stylesheet, href: https://example.com/style.css, title: \"Print (A4)\");
+    title 'Hello'
+}
+ul ['a', 'b']
+";
+
+        let (_, expected) = parse(expected).expect("parse braces");
+        let (_, got) = parse(&braces(indented)).expect("parse indented");
+
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn doc_comments() {
+        let (_, body) = parse(&braces("div\n    p 'x'\n/// docs\nspan 'y'")).expect("parse");
+        let Some(crate::Element::Node(span)) = body.get(1) else {
+            panic!("expected span");
+        };
+
+        assert_eq!(span.doc.as_deref(), Some("docs"));
+    }
+}
@@ -1,5 +1,10 @@
 use nom::bytes::complete::take;
+use nom::branch::alt;
+use nom::combinator::{map, opt, peek};
+use nom::multi::many0;
+use nom::sequence::terminated;
 
+mod scan;
 mod string_inline;
 pub use string_inline::*;
 mod ident;
@@ -17,6 +22,112 @@ pub use node::*;
 mod element;
 pub use element::Element;
 
+mod component;
+pub use component::Component;
+
+mod include;
+pub use include::{Extends, Import, Include};
+
+mod block;
+pub use block::Block;
+
+mod condition;
+pub use condition::{Condition, Conditional};
+
+mod comment;
+pub use comment::HtmlComment;
+
+mod list;
+pub use list::List;
+
+mod table;
+pub use table::Rows;
+
+mod raw;
+pub use raw::Raw;
+
+mod style;
+pub use style::StyleBlock;
+
+mod markup;
+
+mod markdown;
+
+mod indented;
+pub use indented::braces;
+
+mod pragma;
+pub use pragma::{pragmas, Pragmas, EDITION};
+
+mod filter;
+pub use filter::{Filter, FilterCall, Filters, Interpolation};
+
+mod expand;
+pub use expand::*;
+
+/// Parses a whole file.
+/// On the top level any number of elements may follow each other,
+/// e.g. component definitions next to the html root node.
+/// Files starting with `#!alloy strict` or `#!alloy edition=2`
+/// are parsed in strict mode, see `parse_strict`.
+/// `#!alloy indent` files are parsed like .ialloy files, see `braces`.
+pub fn parse(input: &str) -> nom::IResult<&str, Body> {
+    // the doctype is read by `pragmas`
+    let (body, pragmas) = opt(Pragmas::parse_trim)(input)?;
+    let mode = Mode {
+        strict: is_strict() || pragmas.as_ref().is_some_and(|p| p.strict),
+        // without a header, e.g. for a line of an indented file, the edition stays the same
+        edition: pragmas.as_ref().map_or_else(edition, |p| p.edition),
+    };
+    if !pragmas.is_some_and(|p| p.indented) {
+        return with_mode(mode, || parse_body(body));
+    }
+
+    with_mode(mode, || {
+        let converted = braces(body);
+        match parse_body(&converted) {
+            Ok((_, body)) => Ok((&input[input.len()..], body)),
+            // errors point at the same line of the original input
+            Err(e) => Err(e.map_input(|rest| {
+                let line = rest.lines().next().unwrap_or_default().trim();
+                match input.find(line) {
+                    Some(i) if !line.is_empty() => &input[i..],
+                    _ => input,
+                }
+            })),
+        }
+    })
+}
+
+fn parse_body(input: &str) -> nom::IResult<&str, Body> {
+    fn end(input: &str) -> nom::IResult<&str, ()> {
+        let (input, _eolmarker) = KeywordEof::parse_trim(input)?;
+        nom::combinator::not(take(1usize))(input)
+    }
+
+    // a single body, e.g. { ... } or ;
+    let block = alt((
+        map(KeywordCurlyOpen::parse_trim, |_| ()),
+        map(KeywordNone::parse_trim, |_| ()),
+    ));
+    if peek(block)(input).is_ok() {
+        if let Ok((input, body)) = terminated(Body::parse_trim, end)(input) {
+            return Ok((input, body));
+        }
+    }
+
+    terminated(
+        many0(terminated(Element::parse_trim, element_separator)),
+        end,
+    )(input)
+}
+
+/// Like `parse`, but rejects sloppy syntax:
+/// unquoted values with spaces, commas between elements, unterminated comments and $strings.
+pub fn parse_strict(input: &str) -> nom::IResult<&str, Body> {
+    strictly(|| parse(input))
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::read_dir;
@@ -29,12 +140,15 @@ mod tests {
             let entry = entry.expect("read entry");
             let name = entry.file_name();
             let name = name.to_str().unwrap();
-            if !name.ends_with(".alloy") {
+            if !name.ends_with(".alloy") && !name.ends_with(".ialloy") {
                 continue;
             }
 
-            let content = std::fs::read_to_string(format!("./samples/{name}"))
+            let mut content = std::fs::read_to_string(format!("./samples/{name}"))
                 .expect("read alloy samplefile");
+            if name.ends_with(".ialloy") {
+                content = braces(&content);
+            }
 
             let res = parse(&content);
             assert!(res.is_ok(), "Parsing {name}. Result is: {res:#?}");
@@ -148,14 +262,15 @@ mod tests {
             attributes: Some(Attributes(vec![
                 Attribute {
                     key: Ident("src".to_string()),
-                    value: Some("../resources/icon.png".to_string()),
+                    value: Some(AttributeValue::Expression("../resources/icon.png".to_string())),
                 },
                 Attribute {
                     key: Ident("onclick".to_string()),
-                    value: Some("goto('home')".to_string()),
+                    value: Some(AttributeValue::Expression("goto('home')".to_string())),
                 },
             ])),
             body: Body::default(),
+            ..Default::default()
         };
 
         let Ok((rest, mut result)) = parse(input) else {
@@ -283,7 +398,7 @@ mod tests {
             a,
             Attribute {
                 key: Ident::from_s("src"),
-                value: Some(String::from("../ressources/icon.png"))
+                value: Some(AttributeValue::Expression(String::from("../ressources/icon.png")))
             }
         );
     }
@@ -296,7 +411,7 @@ mod tests {
             a,
             Attribute {
                 key: Ident::from_s("onclick"),
-                value: Some(String::from("goto('home')"))
+                value: Some(AttributeValue::Expression(String::from("goto('home')")))
             }
         );
     }
@@ -332,9 +447,10 @@ mod tests {
             ids_and_classes: vec![IdOrClass::from_s("#header"), IdOrClass::from_s(".w-100")],
             attributes: Some(Attributes(vec![Attribute {
                 key: Ident::from_s("style"),
-                value: Some(String::from("height: 48px; margin-top: 8px")),
+                value: Some(AttributeValue::String(String::from("height: 48px; margin-top: 8px"))),
             }])),
             body: Body::default(),
+            ..Default::default()
         };
 
         let result = Node::from_s(input);
@@ -432,6 +548,373 @@ mod tests {
         assert_eq!(r.0, i);
     }
 
+    #[test]
+    fn html_comments() {
+        let i = "/*! header */ div { comment 'injection point'; /* dropped */ p 'x' } /*! open";
+        let expected = vec![
+            Element::Comment(HtmlComment(String::from(" header "))),
+            Element::Node(Node {
+                kind: Ident::from_s("div"),
+                body: vec![
+                    Element::Comment(HtmlComment(String::from("injection point"))),
+                    Element::Node(Node::from_s("p 'x'")),
+                ],
+                ..Default::default()
+            }),
+            Element::Comment(HtmlComment(String::from(" open"))),
+        ];
+
+        let (rest, result) = parse(i).expect("parse html comments");
+        assert_eq!(rest, "");
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn raw_html() {
+        let i = "div { raw '<b>bold</b>'; raw\"<br>\" }";
+        let expected = Node {
+            kind: Ident::from_s("div"),
+            body: vec![
+                Element::Raw(Raw(String::from("<b>bold</b>"))),
+                Element::Raw(Raw(String::from("<br>"))),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(Node::from_s(i), expected);
+    }
+
+    #[test]
+    fn lists() {
+        assert_eq!(
+            Node::from_s("ul ['Home', About us , \"Contact\",]"),
+            Node::from_s("ul { li 'Home' li 'About us' li 'Contact' }")
+        );
+        assert_eq!(
+            Node::from_s("select(name: x) [a, b]"),
+            Node::from_s("select(name: x) { option 'a' option 'b' }")
+        );
+        assert_eq!(
+            Attribute::from_s("class: [a, 'b', c]"),
+            Attribute {
+                key: Ident::from_s("class"),
+                value: Some(AttributeValue::List(vec![
+                    String::from("a"),
+                    String::from("b"),
+                    String::from("c"),
+                ])),
+            }
+        );
+        assert_eq!(List::from_s("[]"), List(Vec::new()));
+        assert_eq!(
+            Attributes::from_s("(x-data: [1, 2].length, y: [3])").0[0].value,
+            Some(AttributeValue::Expression(String::from("[1, 2].length")))
+        );
+    }
+
+    #[test]
+    fn attribute_values() {
+        let value = |s| AttributeValue::from_s(s);
+
+        assert_eq!(value("'true'"), AttributeValue::String(String::from("true")));
+        assert_eq!(value("true"), AttributeValue::Bool(true));
+        assert_eq!(value("false"), AttributeValue::Bool(false));
+        assert_eq!(value("-1.5"), AttributeValue::Number(String::from("-1.5")));
+        assert_eq!(value("42"), AttributeValue::Number(String::from("42")));
+        assert_eq!(value("inf"), AttributeValue::Expression(String::from("inf")));
+        assert_eq!(
+            value("100%"),
+            AttributeValue::Expression(String::from("100%"))
+        );
+        assert_eq!(value("[a, b]").to_string(), "a b");
+        assert_eq!(value("${d}"), AttributeValue::Expression(String::from("${d}")));
+    }
+
+    #[test]
+    fn style_maps() {
+        let value = AttributeValue::from_s("(height: 48px, margin-top: '8px')");
+        assert_eq!(value.to_string(), "height: 48px; margin-top: 8px");
+        // without values it's still just an expression
+        assert_eq!(
+            AttributeValue::from_s("(x)"),
+            AttributeValue::Expression(String::from("(x)"))
+        );
+        // a map, except for an entry without value
+        assert!(matches!(
+            AttributeValue::parse("(font-family: Arial, sans-serif)"),
+            Err(nom::Err::Failure(_))
+        ));
+
+        let Element::Style(style) = Element::from_s("style { height: 48px; color: red }") else {
+            panic!("expected style block");
+        };
+        assert_eq!(
+            AttributeValue::Map(style.0).to_string(),
+            "height: 48px; color: red"
+        );
+        let Element::Style(style) = Element::from_s(
+            "style { background: url(data:image/png;base64,iVBO) no-repeat; content: '}' }",
+        ) else {
+            panic!("expected style block");
+        };
+        assert_eq!(
+            AttributeValue::Map(style.0).to_string(),
+            "background: url(data:image/png;base64,iVBO) no-repeat; content: '}'"
+        );
+        assert!(matches!(
+            Element::from_s("style { 'p { color: red; }' }"),
+            Element::Node(_)
+        ));
+    }
+
+    #[test]
+    fn grouped_attributes() {
+        let attrs =
+            Attributes::from_s("(data: (user-id: 7, role: admin), aria: (hidden: true), data: x)");
+        let keys: Vec<_> = attrs.0.iter().map(|a| a.key.0.as_str()).collect();
+
+        assert_eq!(keys, ["data-user-id", "data-role", "aria-hidden", "data"]);
+        assert_eq!(
+            attrs.0[0].value,
+            Some(AttributeValue::Number(String::from("7")))
+        );
+    }
+
+    #[test]
+    fn framework_attributes() {
+        let attrs = Attributes::from_s(
+            "(@click.prevent: 'open = true', :class: active, x-on:click: toggle(), 'v-bind:href': url, hx-get: /items, x:'noob')",
+        );
+        let keys: Vec<_> = attrs.0.iter().map(|a| a.key.0.as_str()).collect();
+
+        assert_eq!(
+            keys,
+            ["@click.prevent", ":class", "x-on:click", "v-bind:href", "hx-get", "x"]
+        );
+        for key in ["'a b'", "\"x>y\"", "'on=1'", "''"] {
+            assert!(Attributes::parse(&format!("({key}: 1)")).is_err(), "{key}");
+        }
+        assert_eq!(
+            attrs.0[0].value,
+            Some(AttributeValue::String(String::from("open = true")))
+        );
+
+        let attrs = Attributes::from_s("(x-show:open, v-slot:header)");
+        assert_eq!(attrs.0[0].key.0, "x-show");
+        assert_eq!(attrs.0[0].value, Some(AttributeValue::from_s("open")));
+        assert_eq!(attrs.0[1].key.0, "v-slot:header");
+    }
+
+    #[test]
+    fn bracketed_values() {
+        let attrs = Attributes::from_s("(onclick: f(g(1), ')'), x-data: { open: [1, 2] }, a: b)");
+        let values: Vec<_> = attrs
+            .0
+            .iter()
+            .map(|a| a.value.as_ref().map(|v| v.to_string()))
+            .collect();
+
+        assert_eq!(
+            values,
+            [
+                Some(String::from("f(g(1), ')')")),
+                Some(String::from("{ open: [1, 2] }")),
+                Some(String::from("b")),
+            ]
+        );
+
+        let node = Node::from_s("a(title: (Don't do this));");
+        let value = node.attributes.expect("attributes").0[0].value.clone();
+        assert_eq!(
+            value.map(|v| v.to_string()),
+            Some(String::from("(Don't do this)"))
+        );
+    }
+
+    #[test]
+    fn utility_classes() {
+        let node = Node::from_s(r#"div.md:flex.w-1/2.hover:bg-red-500.[&>p]:mt-2.bg-[#fff]."w-1.5" ['a']"#);
+        let classes: Vec<_> = node
+            .ids_and_classes
+            .iter()
+            .map(|c| match c {
+                IdOrClass::Class(c) => c.0.as_str(),
+                IdOrClass::Id(i) => panic!("unexpected id {}", i.0),
+            })
+            .collect();
+
+        assert_eq!(
+            classes,
+            ["md:flex", "w-1/2", "hover:bg-red-500", "[&>p]:mt-2", "bg-[#fff]", "w-1.5"]
+        );
+        assert_eq!(node.body.len(), 1);
+
+        // the list still is the body
+        let node = Node::from_s("ul.nav['a', 'b']");
+        assert_eq!(node.ids_and_classes, vec![IdOrClass::from_s(".nav")]);
+        assert_eq!(node.body.len(), 2);
+    }
+
+    #[test]
+    fn unicode_idents() {
+        let node = Node::from_s("straßen-karte#übersicht.größe(ß: 1);");
+        assert_eq!(node.kind, Ident::from_s("straßen-karte"));
+        assert_eq!(
+            node.ids_and_classes,
+            vec![IdOrClass::from_s("#übersicht"), IdOrClass::from_s(".größe")]
+        );
+
+        let tag_name = |s| Ident::from_s(s).is_tag_name();
+        assert!(tag_name("h1"));
+        assert!(tag_name("DIV"));
+        assert!(tag_name("my-element"));
+        assert!(tag_name("straßen-karte"));
+        assert!(!tag_name("straßenkarte"));
+        assert!(!tag_name("My-element"));
+        assert!(!tag_name("1-element"));
+        assert!(tag_name("annotation-xml"));
+        assert!(tag_name("font-face"));
+    }
+
+    #[test]
+    fn whitespace_control() {
+        let whitespace = |s| Node::from_s(s).whitespace;
+
+        assert_eq!(whitespace("a 'x'"), Whitespace::default());
+        assert_eq!(
+            whitespace("a< 'x'"),
+            Whitespace {
+                trim_inside: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            whitespace("span.x(a: b)> 'x'"),
+            Whitespace {
+                trim_outside: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            whitespace("b<> 'x'"),
+            Whitespace {
+                trim_inside: true,
+                trim_outside: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            whitespace("span>+ 'x'"),
+            Whitespace {
+                trim_outside: true,
+                space_outside: true,
+                ..Default::default()
+            }
+        );
+
+        let body = Body::from_s("{ 'a'> 'b'+ 'c' }");
+        assert_eq!(
+            body,
+            vec![
+                Element::MarkedText(MarkedText {
+                    body: vec![Element::Text(String::from("a"))],
+                    whitespace: Whitespace {
+                        trim_outside: true,
+                        ..Default::default()
+                    },
+                }),
+                Element::MarkedText(MarkedText {
+                    body: vec![Element::Text(String::from("b"))],
+                    whitespace: Whitespace {
+                        space_outside: true,
+                        ..Default::default()
+                    },
+                }),
+                Element::Text(String::from("c")),
+            ]
+        );
+        assert!(Body::parse("{ 'a'< }").is_err());
+    }
+
+    #[test]
+    fn top_level_body() {
+        assert_eq!(parse("{ div; p; }").map(|(_, body)| body.len()), Ok(2));
+        assert_eq!(parse(";").map(|(_, body)| body.len()), Ok(0));
+        assert_eq!(parse("div; p;").map(|(_, body)| body.len()), Ok(2));
+    }
+
+    #[test]
+    fn strict_mode() {
+        let sloppy = [
+            "div(alt: hello world);",
+            "html { head;, body; }",
+            "div; /* unterminated",
+            "div; /*! unterminated",
+            "div(title: $x);",
+        ];
+        for i in sloppy {
+            assert!(parse(i).is_ok(), "expected to parse {i}");
+            assert!(parse_strict(i).is_err(), "expected strict mode to reject {i}");
+        }
+
+        let strict = "html { head; body(onclick: f(1, 2), alt: 'hello world') /* c */ }";
+        assert_eq!(parse_strict(strict), parse(strict));
+
+        assert!(parse("#!alloy strict\nhtml { head;, body; }").is_err());
+        assert!(std::panic::catch_unwind(|| strictly(|| panic!("while parsing"))).is_err());
+        assert!(!is_strict(), "strict mode is reset after a panic");
+    }
+
+    #[test]
+    fn nested_comments() {
+        let (rest, body) = parse("#!alloy edition=2\n/* a /* b */ c */ div; /*! d /* e */ f */")
+            .expect("parse");
+        assert_eq!(rest, "");
+        assert_eq!(
+            body[1],
+            Element::Comment(HtmlComment(String::from(" d /* e */ f ")))
+        );
+        assert!(parse("#!alloy edition=2\n/* a /* b */ div;").is_err());
+
+        // comments don't nest in edition 1
+        let (_, body) = parse("/* assets live in img/*.png */\nhtml { body { p 'hello' } }")
+            .expect("parse edition 1");
+        assert_eq!(body, Body::from_s("html { body { p 'hello' } }"));
+    }
+
+    #[test]
+    fn doc_comments() {
+        let i = "
+            /// The header.
+            ///Shown on every page.
+            //// not a doc comment
+            header { /// Home link
+                a(href: /) 'home'
+            }
+            /// dropped, since nothing follows
+        ";
+        let (_, body) = parse(i).expect("parse");
+        let Element::Node(header) = &body[0] else {
+            panic!("expected node");
+        };
+        let Element::Node(link) = &header.body[0] else {
+            panic!("expected node");
+        };
+
+        assert_eq!(
+            header.doc.as_deref(),
+            Some("The header.\nShown on every page.")
+        );
+        assert_eq!(link.doc.as_deref(), Some("Home link"));
+        assert_eq!(body.len(), 1);
+
+        let Element::Component(card) = Element::from_s("/// Card docs\ncomponent Card(t) { p '${t}' }")
+        else {
+            panic!("expected component");
+        };
+        assert_eq!(card.doc.as_deref(), Some("Card docs"));
+    }
+
     #[test]
     fn comments2() {
         let i = "/* hello */ input(type: text); /* yeah */";
@@ -447,13 +930,3 @@ mod tests {
         assert_eq!(rest, "", "not rest on {i}");
     }
 }
-
-pub fn parse(input: &str) -> nom::IResult<&str, Body> {
-    let (input, body) = Body::parse_trim(input)?;
-
-    let (input, _eolmarker) = KeywordEof::parse_trim(input)?;
-
-    nom::combinator::not(take(1usize))(input)?;
-
-    Ok((input, body))
-}
@@ -0,0 +1,65 @@
+use nom::{
+    branch::alt,
+    bytes::complete::take_while1,
+    character::complete::multispace0,
+    combinator::{map, opt},
+    multi::separated_list0,
+    sequence::{delimited, preceded, terminated},
+};
+
+use crate::{keywords::*, Element, Ident, Node, Parser};
+
+/// ['Home', 'About', Contact]
+///
+/// Items may be strings or bare words.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct List(pub Vec<String>);
+
+impl Parser for List {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        fn item(input: &str) -> nom::IResult<&str, String> {
+            alt((
+                String::parse,
+                map(
+                    take_while1(|c| !matches!(c, ',' | '[' | ']' | '\'' | '"' | '`')),
+                    |s: &str| s.trim_end().to_string(),
+                ),
+            ))(input)
+        }
+
+        map(
+            delimited(
+                KeywordBracketOpen::parse,
+                terminated(
+                    separated_list0(KeywordComma::parse_trim, preceded(multispace0, item)),
+                    opt(KeywordComma::parse_trim),
+                ),
+                KeywordBracketClose::parse_trim,
+            ),
+            List,
+        )(input)
+    }
+}
+
+impl List {
+    /// Child nodes of `parent`, one per item.
+    ///
+    /// ul ['Home', 'About'] is the same as ul { li 'Home' li 'About' }
+    pub fn into_children(self, parent: &Ident) -> Vec<Element> {
+        let kind = match parent.as_str() {
+            "select" | "datalist" | "optgroup" => "option",
+            _ => "li",
+        };
+
+        self.0
+            .into_iter()
+            .map(|item| {
+                Element::Node(Node {
+                    kind: Ident(kind.to_string()),
+                    body: vec![Element::Text(item)],
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,249 @@
+use pulldown_cmark::{CodeBlockKind, Event, Options, Tag};
+
+use crate::{
+    markup::INLINE, Attribute, AttributeValue, Attributes, Body, Element, IdOrClass, Ident, Node,
+    Raw,
+};
+
+/// Converts CommonMark to elements, so that they're escaped and indented like any other.
+///
+/// # Title {#intro .big}   -> h1#intro.big 'Title'
+///
+/// Html inside of the markdown is kept as raw html.
+pub(crate) fn markdown_to_body(source: &str) -> Body {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_HEADING_ATTRIBUTES;
+    let source = dedent(source);
+
+    let mut stack = vec![Node::default()];
+    // number of nodes each open tag pushed, e.g. pre and code for code blocks
+    let mut pushed = Vec::new();
+    let mut in_head = false;
+
+    for event in pulldown_cmark::Parser::new_ext(&source, options) {
+        match event {
+            Event::Start(tag) => {
+                if let Tag::TableHead = tag {
+                    in_head = true;
+                }
+                let nodes = start(tag, in_head);
+                pushed.push(nodes.len());
+                stack.extend(nodes);
+            }
+            Event::End(tag) => {
+                if let Tag::TableHead = tag {
+                    in_head = false;
+                }
+                for _ in 0..pushed.pop().unwrap_or_default() {
+                    let mut node = stack.pop().expect("started node");
+                    if let Tag::Image(..) = tag {
+                        alt_text(&mut node);
+                    }
+                    push(&mut stack, Element::Node(node));
+                }
+            }
+            Event::Text(text) => push_text(&mut stack, &text),
+            Event::SoftBreak => push_text(&mut stack, "\n"),
+            Event::Code(code) => push(
+                &mut stack,
+                inline("code", Vec::new(), vec![Element::Text(code.to_string())]),
+            ),
+            // every line of an html block is an event of its own
+            Event::Html(html) => {
+                let html = html.trim_end_matches('\n').to_string();
+                push(&mut stack, Element::Raw(Raw(html)));
+            }
+            Event::HardBreak => push(&mut stack, inline("br", Vec::new(), Body::new())),
+            Event::Rule => push(&mut stack, Element::Node(node("hr", Vec::new()))),
+            Event::TaskListMarker(checked) => {
+                let mut attrs = vec![attribute("type", "checkbox"), flag("disabled")];
+                if checked {
+                    attrs.push(flag("checked"));
+                }
+                push(&mut stack, inline("input", attrs, Body::new()));
+            }
+            Event::FootnoteReference(name) => push_text(&mut stack, &format!("[{name}]")),
+        }
+    }
+
+    stack.pop().map(|root| root.body).unwrap_or_default()
+}
+
+/// Nodes opened by `tag`, outermost first.
+fn start(tag: Tag, in_head: bool) -> Vec<Node> {
+    let block = |kind| vec![node(kind, Vec::new())];
+    match tag {
+        Tag::Paragraph => block("p"),
+        Tag::Heading(level, id, classes) => {
+            let mut heading = node(&level.to_string(), Vec::new());
+            heading
+                .ids_and_classes
+                .extend(id.map(|id| IdOrClass::Id(Ident(id.to_string()))));
+            heading.ids_and_classes.extend(
+                classes
+                    .into_iter()
+                    .map(|class| IdOrClass::Class(Ident(class.to_string()))),
+            );
+            vec![heading]
+        }
+        Tag::BlockQuote => block("blockquote"),
+        // the content of pre is written as it is
+        Tag::CodeBlock(kind) => {
+            let attrs = match kind {
+                CodeBlockKind::Fenced(lang) if !lang.is_empty() => {
+                    vec![attribute("class", &format!("language-{lang}"))]
+                }
+                _ => Vec::new(),
+            };
+            let mut pre = node("pre", Vec::new());
+            pre.whitespace = INLINE;
+            vec![pre, node("code", attrs)]
+        }
+        Tag::List(None) => block("ul"),
+        Tag::List(Some(1)) => block("ol"),
+        Tag::List(Some(start)) => vec![node("ol", vec![attribute("start", &start.to_string())])],
+        Tag::Item => block("li"),
+        Tag::FootnoteDefinition(_) => block("div"),
+        Tag::Table(_) => block("table"),
+        Tag::TableHead => vec![node("thead", Vec::new()), node("tr", Vec::new())],
+        Tag::TableRow => block("tr"),
+        Tag::TableCell if in_head => block("th"),
+        Tag::TableCell => block("td"),
+        Tag::Emphasis => vec![inline_node("em", Vec::new())],
+        Tag::Strong => vec![inline_node("strong", Vec::new())],
+        Tag::Strikethrough => vec![inline_node("del", Vec::new())],
+        Tag::Link(_, href, title) => {
+            let mut attrs = vec![attribute("href", &href)];
+            if !title.is_empty() {
+                attrs.push(attribute("title", &title));
+            }
+            vec![inline_node("a", attrs)]
+        }
+        Tag::Image(_, src, title) => {
+            let mut attrs = vec![attribute("src", &src)];
+            if !title.is_empty() {
+                attrs.push(attribute("title", &title));
+            }
+            vec![inline_node("img", attrs)]
+        }
+    }
+}
+
+/// ![alt text](src) -> img(src: src, alt: 'alt text')
+fn alt_text(img: &mut Node) {
+    let alt: String = std::mem::take(&mut img.body)
+        .into_iter()
+        .filter_map(|e| match e {
+            Element::Text(t) => Some(t),
+            _ => None,
+        })
+        .collect();
+    let attrs = img.attributes.get_or_insert_with(Attributes::default);
+    attrs.0.push(attribute("alt", &alt));
+}
+
+fn push(stack: &mut [Node], element: Element) {
+    let parent = stack.last_mut().expect("root node");
+    parent.body.push(element);
+}
+
+/// Text is split up at special characters by the markdown parser, it's joined again here.
+fn push_text(stack: &mut [Node], text: &str) {
+    let parent = stack.last_mut().expect("root node");
+    match parent.body.last_mut() {
+        Some(Element::Text(t)) => t.push_str(text),
+        _ => parent.body.push(Element::Text(text.to_string())),
+    }
+}
+
+fn node(kind: &str, attributes: Vec<Attribute>) -> Node {
+    Node {
+        kind: Ident(kind.to_string()),
+        attributes: (!attributes.is_empty()).then_some(Attributes(attributes)),
+        ..Default::default()
+    }
+}
+
+fn inline_node(kind: &str, attributes: Vec<Attribute>) -> Node {
+    Node {
+        whitespace: INLINE,
+        ..node(kind, attributes)
+    }
+}
+
+fn inline(kind: &str, attributes: Vec<Attribute>, body: Body) -> Element {
+    Element::Node(Node {
+        body,
+        ..inline_node(kind, attributes)
+    })
+}
+
+fn attribute(key: &str, value: &str) -> Attribute {
+    Attribute {
+        key: Ident(key.to_string()),
+        value: Some(AttributeValue::String(value.to_string())),
+    }
+}
+
+fn flag(key: &str) -> Attribute {
+    Attribute {
+        key: Ident(key.to_string()),
+        value: None,
+    }
+}
+
+/// Removes the indentation all lines have in common,
+/// since markdown inside of an alloy string is usually indented along with the code.
+fn dedent(source: &str) -> String {
+    let indentation = source
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or_default();
+
+    source
+        .lines()
+        .map(|line| line.get(indentation..).unwrap_or_else(|| line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod markdown_tests {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn markdown() {
+        let body = markdown_to_body(
+            "
+            # Title {#intro}
+
+            Read the *docs*
+            at [alloy](/docs).
+            ",
+        );
+        let expected = Body::from_s(
+            "{
+                h1#intro 'Title'
+                p { 'Read the ' em<> 'docs' '\nat ' a(href: '/docs')<> 'alloy' '.' }
+            }",
+        );
+
+        assert_eq!(body, expected);
+    }
+
+    #[test]
+    fn code_blocks() {
+        let body = markdown_to_body("```rust\nlet x = 1 < 2;\n```\n\n<hr>\n");
+        let expected = Body::from_s(
+            "{
+                pre<> code(class: 'language-rust') 'let x = 1 < 2;\n'
+                raw '<hr>'
+            }",
+        );
+
+        assert_eq!(body, expected);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{Body, Element, Ident, Node, Parser, Whitespace};
+
+/// Elements whose text is taken literally, without looking for inline markup.
+const PLAIN_TEXT_ELEMENTS: [&str; 7] = [
+    "script", "style", "textarea", "title", "code", "pre", "markdown",
+];
+
+/// Inline nodes are written on the same line as the surrounding text.
+pub(crate) const INLINE: Whitespace = Whitespace {
+    trim_inside: true,
+    trim_outside: true,
+    space_outside: false,
+};
+
+/// Replaces the text of a body with text and inline nodes, unless `kind` takes plain text.
+pub(crate) fn expand_markup(kind: &Ident, body: Body) -> Body {
+    if PLAIN_TEXT_ELEMENTS.contains(&kind.as_str()) {
+        return body;
+    }
+
+    body.into_iter()
+        .flat_map(|element| match element {
+            Element::Text(text) => markup(&text),
+            Element::If(mut conditional) => {
+                conditional.then = expand_markup(kind, conditional.then);
+                conditional.otherwise = expand_markup(kind, conditional.otherwise);
+                vec![Element::If(conditional)]
+            }
+            Element::Block(mut block) => {
+                block.body = expand_markup(kind, block.body);
+                vec![Element::Block(block)]
+            }
+            Element::MarkedText(mut marked) => {
+                marked.body = expand_markup(kind, marked.body);
+                vec![Element::MarkedText(marked)]
+            }
+            element => vec![element],
+        })
+        .collect()
+}
+
+/// p "Read the *docs* at {a(href: /docs) 'here'}"
+///
+/// *emphasis*, **strong**, `code` and {nodes} become nodes of their own.
+/// \* \` \{ and \\ are taken literally, so is ${interpolation}.
+fn markup(text: &str) -> Body {
+    let mut body = Body::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some((element, after)) = inline_element(rest) {
+            if !plain.is_empty() {
+                body.push(Element::Text(std::mem::take(&mut plain)));
+            }
+            body.push(element);
+            rest = after;
+            continue;
+        }
+
+        let len = match c {
+            '\\' if rest[1..].starts_with(['*', '`', '{', '\\']) => {
+                plain.push_str(&rest[1..2]);
+                2
+            }
+            '$' if rest.starts_with("${") => {
+                let len = rest.find('}').map_or(rest.len(), |i| i + 1);
+                plain.push_str(&rest[..len]);
+                len
+            }
+            c => {
+                plain.push(c);
+                c.len_utf8()
+            }
+        };
+        rest = &rest[len..];
+    }
+
+    if !plain.is_empty() {
+        body.push(Element::Text(plain));
+    }
+
+    body
+}
+
+fn inline_element(input: &str) -> Option<(Element, &str)> {
+    if let Some(input) = input.strip_prefix("**") {
+        let (content, rest) = delimited_by(input, "**")?;
+        return Some((inline_node("strong", markup(content)), rest));
+    }
+    if let Some(input) = input.strip_prefix('*') {
+        let (content, rest) = delimited_by(input, "*")?;
+        return Some((inline_node("em", markup(content)), rest));
+    }
+    if let Some(input) = input.strip_prefix('`') {
+        let (content, rest) = delimited_by(input, "`")?;
+        let content = vec![Element::Text(content.to_string())];
+        return Some((inline_node("code", content), rest));
+    }
+    if let Some(input) = input.strip_prefix('{') {
+        let (rest, mut node) = Node::parse_trim(input).ok()?;
+        let rest = rest.trim_start().strip_prefix('}')?;
+        node.whitespace = INLINE;
+        return Some((Element::Node(node), rest));
+    }
+    None
+}
+
+/// Content up to the closing `delimiter`. Neither side of the content may be whitespace.
+fn delimited_by<'a>(input: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let end = input.find(delimiter)?;
+    let content = &input[..end];
+    if content.is_empty()
+        || content.starts_with(char::is_whitespace)
+        || content.ends_with(char::is_whitespace)
+    {
+        return None;
+    }
+    Some((content, &input[end + delimiter.len()..]))
+}
+
+fn inline_node(kind: &str, body: Body) -> Element {
+    Element::Node(Node {
+        kind: Ident(kind.to_string()),
+        whitespace: INLINE,
+        body,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod markup_tests {
+    use super::*;
+
+    #[test]
+    fn inline_markup() {
+        let body = markup("Read the *docs* at {a(href: /docs) 'here'}, **now**.");
+        let expected = Body::from_s(
+            "{ 'Read the ' em<> 'docs' ' at ' a(href: /docs)<> 'here' ', ' strong<> 'now' '.' }",
+        );
+
+        assert_eq!(body, expected);
+    }
+
+    #[test]
+    fn literal_text() {
+        let text = |s: &str| vec![Element::Text(s.to_string())];
+
+        assert_eq!(markup("5 * 3 * 2"), text("5 * 3 * 2"));
+        assert_eq!(
+            markup("${a} {not a node} \\*x\\*"),
+            text("${a} {not a node} *x*")
+        );
+        assert_eq!(markup("C:\\new"), text("C:\\new"));
+    }
+
+    #[test]
+    fn edition() {
+        let body = |s| crate::parse(s).expect("parse").1;
+
+        assert_eq!(body("p 'a *b*'"), Body::from_s("p 'a *b*'"));
+        assert_eq!(
+            body("#!alloy edition=2\np 'a *b*'"),
+            Body::from_s("p { 'a ' em<> 'b' }")
+        );
+    }
+}
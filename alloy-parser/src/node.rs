@@ -1,20 +1,33 @@
+use std::fmt;
+
 use nom::{
     branch::alt,
-    character::complete::char,
-    combinator::{cut, map, opt},
+    bytes::complete::tag,
+    character::complete::{char, multispace0},
+    combinator::{cut, eof, map, opt, peek, success, verify},
     error::context,
     multi::many0,
-    sequence::{delimited, preceded, terminated},
+    sequence::{delimited, pair, preceded, terminated},
 };
 
-use crate::{keywords::*, Body, Ident, Parser, StringInline};
+use crate::{
+    ident::is_ident_char,
+    keywords::*,
+    markup::expand_markup,
+    parser::edition,
+    table::{take_header, Rows},
+    Body, Element, Ident, List, Parser, StringInline,
+};
 
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Node {
     pub kind: Ident,
     pub ids_and_classes: Vec<IdOrClass>,
     pub attributes: Option<Attributes>,
+    pub whitespace: Whitespace,
     pub body: Body,
+    /// Text of the /// comments right before the node. Not written to the output.
+    pub doc: Option<String>,
 }
 impl Parser for Node {
     fn parse(input: &str) -> nom::IResult<&str, Self> {
@@ -22,14 +35,44 @@ impl Parser for Node {
         let (input, ids_and_classes) = many0(IdOrClass::parse_trim)(input)?;
 
         let (input, attributes) = opt(Attributes::parse_trim)(input)?;
+        let (input, whitespace) = opt(Whitespace::parse_trim)(input)?;
+        let whitespace = whitespace.unwrap_or_default();
+
+        // table [[a, b], [1, 2]]
+        if kind.0 == "table" {
+            if let Ok((input, rows)) = Rows::parse_trim(input) {
+                let mut node = Node {
+                    kind,
+                    ids_and_classes,
+                    attributes,
+                    whitespace,
+                    body: Body::new(),
+                    doc: None,
+                };
+                let header = take_header(&mut node);
+                node.body = rows.into_children(header);
+                return Ok((input, node));
+            }
+        }
 
-        // may be one of these 4
+        // may be one of these 5
 
         // div
         // "abcdefg"
         // ;
         // {}
-        let (input, body) = cut(Body::parse_trim)(input)?;
+        // [a, b]
+        let (input, body) = cut(alt((
+            map(List::parse_trim, |list| list.into_children(&kind)),
+            Body::parse_trim,
+        )))(input)?;
+
+        // p "Read the *docs*" -> text and inline nodes, since edition 2.
+        // Older files might use * and { in text literally.
+        let body = match edition() >= 2 {
+            true => expand_markup(&kind, body),
+            false => body,
+        };
 
         Ok((
             input,
@@ -37,12 +80,93 @@ impl Parser for Node {
                 kind,
                 ids_and_classes,
                 attributes,
+                whitespace,
                 body,
+                doc: None,
+            },
+        ))
+    }
+}
+
+impl Node {
+    /// Removes the attribute `key` and returns it.
+    pub fn take_attribute(&mut self, key: &str) -> Option<Attribute> {
+        let attrs = self.attributes.as_mut()?;
+        let index = attrs.0.iter().position(|a| a.key.0 == key)?;
+        let attr = attrs.0.remove(index);
+        if attrs.0.is_empty() {
+            self.attributes = None;
+        }
+        Some(attr)
+    }
+}
+
+/// Whitespace the pretty printer leaves out or adds.
+///
+/// a< 'text'       no whitespace inside: <a>text</a>
+/// span> 'x'       no whitespace around, it's glued to its siblings
+/// b<> 'x'         both
+/// span>+ 'x'      glued to its siblings, but with a space on either side
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Whitespace {
+    pub trim_inside: bool,
+    pub trim_outside: bool,
+    /// A space before and after the node, where there'd be no whitespace otherwise.
+    pub space_outside: bool,
+}
+
+impl Parser for Whitespace {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        let trim = alt((
+            map(alt((tag("<>"), tag("><"))), |_| (true, true)),
+            map(char('<'), |_| (true, false)),
+            map(char('>'), |_| (false, true)),
+            success((false, false)),
+        ));
+        verify(
+            map(
+                pair(trim, opt(char('+'))),
+                |((trim_inside, trim_outside), plus)| Whitespace {
+                    trim_inside,
+                    trim_outside,
+                    space_outside: plus.is_some(),
+                },
+            ),
+            |whitespace| *whitespace != Whitespace::default(),
+        )(input)
+    }
+}
+
+/// Text with whitespace markers, e.g. 'here'> or ' | '+
+///
+/// Text has no inside, so < isn't allowed.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct MarkedText {
+    /// The text, along with the nodes of its inline markup.
+    pub body: Body,
+    pub whitespace: Whitespace,
+}
+
+impl Parser for MarkedText {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        let (input, text) = String::parse(input)?;
+        let (input, whitespace) =
+            verify(Whitespace::parse_trim, |whitespace| !whitespace.trim_inside)(input)?;
+
+        Ok((
+            input,
+            MarkedText {
+                body: vec![Element::Text(text)],
+                whitespace,
             },
         ))
     }
 }
 
+/// (type: text, data: (user-id: 7), aria: (label: 'Close'))
+///
+/// Entries of data and aria maps become attributes of their own,
+/// e.g. data-user-id: 7 and aria-label: 'Close'
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Attributes(pub Vec<Attribute>);
 impl Parser for Attributes {
@@ -56,11 +180,28 @@ impl Parser for Attributes {
                 )),
                 KeywordParenClose::parse_trim,
             ),
-            Attributes,
+            |attrs| Attributes(attrs.into_iter().flat_map(ungroup).collect()),
         )(input)
     }
 }
 
+/// data: (role: admin) -> data-role: admin
+fn ungroup(attr: Attribute) -> Vec<Attribute> {
+    match attr {
+        Attribute {
+            key,
+            value: Some(AttributeValue::Map(entries)),
+        } if key.0 == "data" || key.0 == "aria" => entries
+            .into_iter()
+            .map(|entry| Attribute {
+                key: Ident(format!("{}-{}", key.0, entry.key.0)),
+                value: entry.value,
+            })
+            .collect(),
+        attr => vec![attr],
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IdOrClass {
     Id(Ident),
@@ -86,7 +227,10 @@ impl Parser for IdOrClass {
             map(
                 preceded(
                     char('.'),
-                    context("expect identifier after .", cut(Ident::parse)),
+                    context(
+                        "expect identifier after .",
+                        cut(alt((map(String::parse, Ident), class_name))),
+                    ),
                 ),
                 IdOrClass::Class,
             ),
@@ -94,22 +238,57 @@ impl Parser for IdOrClass {
     }
 }
 
+/// Class names of utility frameworks, e.g.
+/// md:flex, w-1/2, hover:bg-red-500, [&>p]:mt-2 or bg-[#fff]
+///
+/// : and / need to be followed by more of the name, [ needs to start it or follow - or :
+/// Anything else, like w-1.5, has to be quoted: ."w-1.5"
+fn class_name(input: &str) -> nom::IResult<&str, Ident> {
+    let mut end = 0;
+    let mut prev = None;
+    while let Some(c) = input[end..].chars().next() {
+        let rest = &input[end + c.len_utf8()..];
+        let len = match c {
+            c if is_ident_char(c) => c.len_utf8(),
+            ':' | '/' if rest.starts_with(|c| is_ident_char(c) || c == '[') => 1,
+            '[' if matches!(prev, None | Some('-') | Some(':')) => match rest.find(']') {
+                Some(i) => i + 2,
+                None => break,
+            },
+            _ => break,
+        };
+        end += len;
+        prev = input[..end].chars().last();
+    }
+
+    if end == 0 {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::TakeWhile1,
+        )));
+    }
+
+    Ok((&input[end..], Ident(input[..end].to_string())))
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Attribute {
     pub key: Ident,
-    pub value: Option<String>,
+    pub value: Option<AttributeValue>,
 }
 
 impl Parser for Attribute {
     fn parse(input: &str) -> nom::IResult<&str, Self> {
-        let (input, key) = Ident::parse(input)?;
+        let (input, key) = attribute_key(input)?;
 
         let Ok((input, _)) = KeywordColon::parse_trim(input) else {
-            return Ok((input, Attribute { key, value: None }))
+            return Ok((input, Attribute { key, value: None }));
         };
 
-        let (input, StringInline(value)) =
-            context("expected attribute after :", cut(StringInline::parse_trim))(input)?;
+        let (input, value) = context(
+            "expected attribute after :",
+            cut(AttributeValue::parse_trim),
+        )(input)?;
 
         Ok((
             input,
@@ -120,3 +299,169 @@ impl Parser for Attribute {
         ))
     }
 }
+
+/// Keys of framework directives, whose : is part of the name rather than the separator.
+///
+/// Other names, like x-show, keep : as the separator, so x-show:open is x-show with the value open.
+const DIRECTIVES: [&str; 6] = ["x-on", "x-bind", "v-on", "v-bind", "v-slot", "hx-on"];
+
+/// href
+/// '@click' or any other quoted key
+/// @click.prevent
+/// :class
+/// x-on:click.prevent, v-bind:href
+///
+/// A : followed by whitespace always separates key and value.
+fn attribute_key(input: &str) -> nom::IResult<&str, Ident> {
+    if let Ok((rest, key)) = String::parse(input) {
+        // quoted keys are written as they are, so they mustn't end the attribute or the tag
+        if !is_attribute_name(&key) {
+            return Err(nom::Err::Failure(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+        return Ok((rest, Ident(key)));
+    }
+
+    let (input, prefix) = opt(alt((char('@'), char(':'))))(input)?;
+    let (mut input, name) = Ident::parse(input)?;
+
+    let mut key = String::new();
+    key.extend(prefix);
+    key.push_str(&name.0);
+
+    let directive = prefix.is_some() || DIRECTIVES.contains(&key.as_str());
+    let separators: &[char] = if directive { &['.', ':'] } else { &['.'] };
+
+    // modifiers, e.g. .prevent
+    while let Some(c) = input.chars().next().filter(|c| separators.contains(c)) {
+        let Ok((rest, part)) = Ident::parse(&input[1..]) else {
+            break;
+        };
+        key.push(c);
+        key.push_str(&part.0);
+        input = rest;
+    }
+
+    Ok((input, Ident(key)))
+}
+
+/// Attribute names of the html spec: no whitespace, controls or any of "'>/=
+fn is_attribute_name(key: &str) -> bool {
+    !key.is_empty()
+        && !key.chars().any(|c| {
+            c.is_whitespace() || c.is_control() || matches!(c, '"' | '\'' | '>' | '/' | '=')
+        })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeValue {
+    /// 'text', "text", `text` or $text
+    String(String),
+    /// true, false
+    Bool(bool),
+    /// 42, -1.5
+    /// Kept just like it's written.
+    Number(String),
+    /// [a, b]
+    List(Vec<String>),
+    /// (height: 48px, margin-top: 8px)
+    /// Every entry has a value, so that (x) still is an expression.
+    Map(Vec<Attribute>),
+    /// Everything else that isn't quoted, e.g. goto('home') or ../icon.png
+    Expression(String),
+}
+
+impl Default for AttributeValue {
+    fn default() -> Self {
+        AttributeValue::String(String::new())
+    }
+}
+
+impl Parser for AttributeValue {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        // lists and maps have to be the whole value, [1, 2].length is an expression
+        alt((
+            map(terminated(List::parse, value_end), |list| {
+                AttributeValue::List(list.0)
+            }),
+            map(terminated(map_value, value_end), AttributeValue::Map),
+            map(String::parse, AttributeValue::String),
+            map(StringInline::parse, |StringInline(s)| {
+                AttributeValue::typed(s)
+            }),
+        ))(input)
+    }
+}
+
+/// The , or ) after a value, or the end of the input. Isn't consumed.
+fn value_end(input: &str) -> nom::IResult<&str, &str> {
+    peek(preceded(multispace0, alt((tag(","), tag(")"), eof))))(input)
+}
+
+/// (height: 48px, margin-top: 8px)
+///
+/// Entries without a value make it an error, e.g. (font-family: Arial, sans-serif)
+/// would be written as it is otherwise. Without any value at all, e.g. (a, b), it's no map.
+fn map_value(input: &str) -> nom::IResult<&str, Vec<Attribute>> {
+    let (rest, attributes) = Attributes::parse(input)?;
+    let with_value = attributes.0.iter().filter(|a| a.value.is_some()).count();
+    if with_value == 0 {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+    if with_value < attributes.0.len() && value_end(rest).is_ok() {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+    Ok((rest, attributes.0))
+}
+
+impl AttributeValue {
+    /// Type of an unquoted value, e.g. true is a boolean, but 'true' is a string.
+    pub(crate) fn typed(s: String) -> Self {
+        match s.as_str() {
+            "true" => AttributeValue::Bool(true),
+            "false" => AttributeValue::Bool(false),
+            s if is_number(s) => AttributeValue::Number(s.to_string()),
+            _ => AttributeValue::Expression(s),
+        }
+    }
+}
+
+fn is_number(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    digits.starts_with(|c: char| c.is_ascii_digit()) && digits.parse::<f64>().is_ok()
+}
+
+/// The value as it's written to html.
+/// Items of a list are separated by spaces, e.g. class: [a, b] is the same as class: 'a b'
+/// Maps are written as css declarations, e.g. style: (height: 48px) is the same as style: 'height: 48px'
+impl fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeValue::String(s)
+            | AttributeValue::Number(s)
+            | AttributeValue::Expression(s) => f.write_str(s),
+            AttributeValue::Bool(b) => write!(f, "{b}"),
+            AttributeValue::List(items) => f.write_str(&items.join(" ")),
+            AttributeValue::Map(entries) => {
+                for (i, entry) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("; ")?;
+                    }
+                    write!(f, "{}", entry.key.0)?;
+                    if let Some(ref value) = entry.value {
+                        write!(f, ": {value}")?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -1,13 +1,98 @@
+use std::cell::Cell;
+
 use nom::branch::alt;
 use nom::bytes::complete::{take, take_until};
 use nom::character::complete::char;
-use nom::combinator::{cut, map};
+use nom::combinator::{cut, map, not, opt};
 use nom::error::context;
 use nom::sequence::{preceded, terminated};
 
-use crate::keywords::KeywordInline;
+use crate::keywords::{KeywordComma, KeywordInline};
 use crate::StringInline;
 
+/// Language features of the file being parsed, see `Pragmas`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Mode {
+    /// Sloppy syntax is rejected, see `parse_strict`.
+    pub strict: bool,
+    pub edition: u32,
+}
+
+thread_local! {
+    /// Only ever set by `with_mode`, for the duration of a single parse.
+    static MODE: Cell<Mode> = const {
+        Cell::new(Mode {
+            strict: false,
+            edition: 1,
+        })
+    };
+}
+
+/// Whether sloppy syntax is rejected.
+pub(crate) fn is_strict() -> bool {
+    MODE.with(Cell::get).strict
+}
+
+/// Edition of the file being parsed.
+pub(crate) fn edition() -> u32 {
+    MODE.with(Cell::get).edition
+}
+
+/// Runs `f` in strict mode.
+pub(crate) fn strictly<T>(f: impl FnOnce() -> T) -> T {
+    let mode = MODE.with(Cell::get);
+    with_mode(
+        Mode {
+            strict: true,
+            ..mode
+        },
+        f,
+    )
+}
+
+/// Runs `f` with the language features of `mode`.
+pub(crate) fn with_mode<T>(mode: Mode, f: impl FnOnce() -> T) -> T {
+    let _restore = Restore(MODE.with(|m| m.replace(mode)));
+    f()
+}
+
+/// Restores the previous mode, even if parsing panics.
+struct Restore(Mode);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        MODE.with(|m| m.set(self.0));
+    }
+}
+
+/// Optional comma after an element, e.g. head;, body;
+/// Rejected in strict mode.
+pub(crate) fn element_separator(input: &str) -> nom::IResult<&str, ()> {
+    if is_strict() {
+        return context(
+            "unexpected , between elements",
+            cut(not(KeywordComma::parse_trim)),
+        )(input);
+    }
+    map(opt(KeywordComma::parse_trim), |_| ())(input)
+}
+
+/// Fails for good in strict mode.
+pub(crate) fn reject_strict<'a>(
+    input: &'a str,
+    message: &'static str,
+) -> nom::IResult<&'a str, ()> {
+    if !is_strict() {
+        return Ok((input, ()));
+    }
+    context(message, |i| {
+        Err(nom::Err::Failure(nom::error::Error::new(
+            i,
+            nom::error::ErrorKind::Verify,
+        )))
+    })(input)
+}
+
 pub trait Parser
 where
     Self: Sized,
@@ -17,22 +102,8 @@ where
     fn parse_trim(input: &str) -> nom::IResult<&str, Self> {
         let input = input.trim_start();
 
-        // cut out commments
-        if input.starts_with("//") {
-            if let Some(index) = input.find('\n') {
-                let index = index + 1;
-                return Self::parse_trim(&input[index..]);
-            }
-            return Self::parse_trim("");
-        }
-        /* cut out comments */
-        if input.starts_with("/*") {
-            if let Some(index) = input.find("*/") {
-                let index = index + 2;
-                return Self::parse_trim(&input[index..]);
-            }
-            // It's allowed to simply cut off all remaining content without closing */
-            return Self::parse_trim("");
+        if let Some(rest) = skip_comment(input) {
+            return Self::parse_trim(rest);
         }
 
         Self::parse(input)
@@ -43,6 +114,77 @@ where
     }
 }
 
+/// Position of the */ closing a block comment, whose /* is right before `input`.
+/// Since edition 2, nested block comments need to be closed as well.
+/// Before, /* assets live in img/*.png */ was one comment.
+pub(crate) fn comment_end(input: &str) -> Option<usize> {
+    if edition() < 2 {
+        return input.find("*/");
+    }
+
+    let bytes = input.as_bytes();
+    let mut depth = 1;
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match &bytes[i..i + 2] {
+            b"/*" => depth += 1,
+            b"*/" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+        i += 2;
+    }
+    None
+}
+
+/// /// documentation of the next node
+///
+/// Returns the text of a single line, without the leading space.
+/// //// is an ordinary comment.
+pub(crate) fn doc_comment(input: &str) -> Option<(&str, &str)> {
+    let line = input.strip_prefix("///")?;
+    if line.starts_with('/') {
+        return None;
+    }
+    let (line, rest) = line.split_once('\n').unwrap_or((line, ""));
+    let line = line.strip_prefix(' ').unwrap_or(line).trim_end();
+    Some((line, rest))
+}
+
+/// Cuts out the comment at the start of `input`, if there is one.
+pub(crate) fn skip_comment(input: &str) -> Option<&str> {
+    // cut out commments
+    if input.starts_with("//") {
+        if let Some(index) = input.find('\n') {
+            let index = index + 1;
+            return Some(&input[index..]);
+        }
+        return Some("");
+    }
+    /* cut out comments, /* nested ones */ as well */
+    if let Some(body) = input.strip_prefix("/*") {
+        if let Some(index) = comment_end(body) {
+            let index = 2 + index + 2;
+            return Some(&input[index..]);
+        }
+        // It's allowed to simply cut off all remaining content without closing */
+        // In strict mode the comment is left in place, which fails to parse.
+        if is_strict() {
+            return None;
+        }
+        return Some("");
+    }
+
+    None
+}
+
 /// "hello world"
 /// 'hello world'
 /// `hello world`
@@ -77,7 +219,15 @@ impl Parser for String {
                 String::from,
             ),
             // TODO is this needed for css? try in production or remove
-            preceded(KeywordInline::parse, map(StringInline::parse, |s| s.0)),
+            // Rejected in strict mode.
+            // ${name} is an interpolation, not a $ string
+            preceded(
+                terminated(KeywordInline::parse, not(char('{'))),
+                preceded(
+                    |i| reject_strict(i, "$ strings aren't allowed in strict mode"),
+                    map(StringInline::parse, |s| s.0),
+                ),
+            ),
         ))(input)
     }
 }
@@ -0,0 +1,127 @@
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while1},
+    character::complete::{digit1, line_ending, space0, space1},
+    combinator::{cut, eof, map, map_res, verify},
+    error::context,
+    multi::many0,
+    sequence::preceded,
+};
+
+use crate::Parser;
+
+/// Header on the first line of a file, switching language features for that file.
+///
+/// #!alloy edition=2 strict doctype=html indent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pragmas {
+    /// Files without a header are edition 1. Edition 2 is strict.
+    pub edition: u32,
+    /// Rejects some sloppy syntax, that's accepted otherwise.
+    pub strict: bool,
+    /// Written as <!DOCTYPE ...> before the output.
+    pub doctype: Option<String>,
+    /// Children are indented below their parent, just like in .ialloy files.
+    pub indented: bool,
+}
+
+/// The newest edition.
+pub const EDITION: u32 = 2;
+
+impl Default for Pragmas {
+    fn default() -> Self {
+        Pragmas {
+            edition: 1,
+            strict: false,
+            doctype: None,
+            indented: false,
+        }
+    }
+}
+
+enum Pragma {
+    Edition(u32),
+    Strict,
+    Doctype(String),
+    Indent,
+}
+
+impl Parser for Pragmas {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        let pragma = alt((
+            map(
+                preceded(
+                    tag("edition="),
+                    context(
+                        "unknown edition",
+                        cut(verify(map_res(digit1, str::parse), |e| {
+                            (1..=EDITION).contains(e)
+                        })),
+                    ),
+                ),
+                Pragma::Edition,
+            ),
+            map(tag("strict"), |_| Pragma::Strict),
+            map(
+                preceded(tag("doctype="), take_while1(|c: char| !c.is_whitespace())),
+                |d: &str| Pragma::Doctype(d.to_string()),
+            ),
+            map(tag("indent"), |_| Pragma::Indent),
+        ));
+
+        let (input, _) = tag("#!alloy")(input)?;
+        let (input, list) = many0(preceded(space1, pragma))(input)?;
+        let (input, _) = preceded(
+            space0,
+            context("unknown pragma", cut(alt((line_ending, eof)))),
+        )(input)?;
+
+        let mut pragmas = Pragmas::default();
+        for pragma in list {
+            match pragma {
+                Pragma::Edition(edition) => pragmas.edition = edition,
+                Pragma::Strict => pragmas.strict = true,
+                Pragma::Doctype(doctype) => pragmas.doctype = Some(doctype),
+                Pragma::Indent => pragmas.indented = true,
+            }
+        }
+        pragmas.strict |= pragmas.edition >= 2;
+
+        Ok((input, pragmas))
+    }
+}
+
+/// Pragmas of a file, or the defaults if it has no header.
+pub fn pragmas(input: &str) -> Pragmas {
+    match Pragmas::parse_trim(input) {
+        Ok((_, pragmas)) => pragmas,
+        Err(_) => Pragmas::default(),
+    }
+}
+
+#[cfg(test)]
+mod pragma_tests {
+    use super::*;
+
+    #[test]
+    fn header() {
+        let expected = Pragmas {
+            edition: 2,
+            strict: true,
+            doctype: Some(String::from("html")),
+            indented: false,
+        };
+
+        assert_eq!(pragmas("#!alloy edition=2 doctype=html\nhtml;"), expected);
+        assert_eq!(pragmas("html;"), Pragmas::default());
+        assert!(Pragmas::parse("#!alloy edition=3").is_err());
+        assert!(Pragmas::parse("#!alloy fancy").is_err());
+
+        let (_, body) = crate::parse("#!alloy doctype=html\nhtml;").expect("parse file");
+        assert_eq!(body.len(), 1);
+
+        let (_, body) = crate::parse("#!alloy indent\nul\n    li 'x'").expect("parse indented");
+        assert_eq!(body, crate::Body::from_s("{ ul { li 'x' } }"));
+        assert!(crate::parse("#!alloy indent\nul\n    li(").is_err());
+    }
+}
@@ -0,0 +1,27 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::multispace0,
+    combinator::{map, opt},
+    sequence::{delimited, terminated},
+};
+
+use crate::{keywords::*, Parser};
+
+/// Html that's written to the output as it is, without escaping.
+///
+/// raw "<iframe src='https://example.com/embed'></iframe>"
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Raw(pub String);
+
+impl Parser for Raw {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        map(
+            delimited(
+                terminated(tag("raw"), multispace0),
+                String::parse,
+                opt(KeywordNone::parse_trim),
+            ),
+            Raw,
+        )(input)
+    }
+}
@@ -0,0 +1,72 @@
+/// Keeps track of quotes and brackets, while walking through a value or a line char by char.
+///
+/// ' only starts a string where a javascript string could start, not right after a letter or digit.
+/// So (Don't do this) has no quote in it, f('a') has.
+/// Inside of quotes, \ escapes the next char.
+#[derive(Default, Debug)]
+pub(crate) struct Scanner {
+    quote: Option<char>,
+    escaped: bool,
+    /// Closing brackets of the open ones, the innermost last.
+    open: Vec<char>,
+    previous: Option<char>,
+}
+
+/// What a char turned out to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Scanned {
+    /// Part of a string, including its quotes.
+    Quoted,
+    Open,
+    Close,
+    /// A closing bracket that doesn't match the open one, e.g. the ] of (1]
+    Unbalanced,
+    /// Anything else outside of quotes.
+    Plain,
+}
+
+impl Scanner {
+    pub(crate) fn next(&mut self, c: char) -> Scanned {
+        let previous = self.previous.replace(c);
+        match (self.quote, c) {
+            (Some(_), _) if self.escaped => self.escaped = false,
+            (Some(_), '\\') => self.escaped = true,
+            (Some(q), c) if c == q => self.quote = None,
+            (Some(_), _) => {}
+            (None, '\'') if previous.is_some_and(char::is_alphanumeric) => return Scanned::Plain,
+            (None, '\'' | '"' | '`') => self.quote = Some(c),
+            (None, '(') => return self.push(')'),
+            (None, '[') => return self.push(']'),
+            (None, '{') => return self.push('}'),
+            (None, ')' | ']' | '}') => {
+                if self.open.last() != Some(&c) {
+                    return Scanned::Unbalanced;
+                }
+                self.open.pop();
+                return Scanned::Close;
+            }
+            _ => return Scanned::Plain,
+        }
+        Scanned::Quoted
+    }
+
+    fn push(&mut self, closing: char) -> Scanned {
+        self.open.push(closing);
+        Scanned::Open
+    }
+
+    /// Whether the last char was inside of a string, or opened one.
+    pub(crate) fn in_quote(&self) -> bool {
+        self.quote.is_some()
+    }
+
+    /// Number of open brackets.
+    pub(crate) fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Whether a ( or [ is open, e.g. of an attribute list or a list.
+    pub(crate) fn in_brackets(&self) -> bool {
+        self.open.iter().any(|&c| c == ')' || c == ']')
+    }
+}
@@ -1,11 +1,13 @@
 use std::ops::Deref;
 
-use crate::Parser;
+use crate::{
+    parser::reject_strict,
+    scan::{Scanned, Scanner},
+    Parser,
+};
 use nom::branch::alt;
-use nom::bytes::complete::{take, take_until, take_while1};
+use nom::bytes::complete::take_while1;
 use nom::character::complete::char;
-use nom::combinator::cut;
-use nom::sequence::delimited;
 
 /// Represents a special syntax by which we can recognize strings inside attributes.
 /// Designed to be most compatible with javascript and respects opening / closing brackets.
@@ -21,42 +23,46 @@ impl Parser for StringInline {
         }
 
         let (rest, s) = recognize(recognize_input_str)(input)?;
+        if has_loose_whitespace(s.trim()) {
+            reject_strict(input, "quote values with spaces in strict mode")?;
+        }
         Ok((rest, StringInline(s.to_string())))
     }
 }
 
-#[cfg(test)]
-mod inline_str_tests {
-    use super::*;
-
-    #[test]
-    fn parens() {
-        let input = "(dhsjakdhsjkadhk   dsjakldjsla  )";
-        let (rest, got) = recognize_input_str(input).expect("parse str");
-
-        assert_eq!(rest, "", "nothing remains");
-        assert_eq!(got, "dhsjakdhsjkadhk   dsjakldjsla");
-    }
-
-    #[test]
-    fn anyparens() {
-        let input = "(dhsjakdhsjkadhk   dsjakldjsla  )";
-        let (rest, got) = anyparen(input).expect("parse str");
-
-        assert_eq!(rest, "", "nothing remains");
-        assert_eq!(got, "dhsjakdhsjkadhk   dsjakldjsla");
-    }
+/// Whitespace outside of brackets and quotes, e.g. hello world but not f(a, b)
+fn has_loose_whitespace(s: &str) -> bool {
+    let mut scanner = Scanner::default();
+    s.chars()
+        .any(|c| scanner.next(c) == Scanned::Plain && c.is_whitespace() && scanner.depth() == 0)
 }
 
+/// Content of a bracketed span, e.g. (f(g(1), ')')).
 fn anyparen(input: &str) -> nom::IResult<&str, &str> {
-    let (rest, got) = alt((
-        delimited(char('('), cut(take_until(")")), take(1usize)),
-        delimited(char('{'), cut(take_until("}")), take(1usize)),
-        delimited(char('['), cut(take_until("]")), take(1usize)),
-    ))(input)?;
+    let (inner, _) = alt((char('('), char('{'), char('[')))(input)?;
+    let Some(end) = closing_bracket(input) else {
+        return Err(nom::Err::Failure(nom::error::Error::new(
+            inner,
+            nom::error::ErrorKind::TakeUntil,
+        )));
+    };
+
+    let got = input[1..end].trim_end();
+    Ok((&input[end + 1..], got))
+}
 
-    let got = got.trim_end();
-    Ok((rest, got))
+/// Index of the bracket closing the one `input` starts with.
+/// Nested brackets are balanced and brackets inside of quotes are skipped.
+fn closing_bracket(input: &str) -> Option<usize> {
+    let mut scanner = Scanner::default();
+    for (i, c) in input.char_indices() {
+        match scanner.next(c) {
+            Scanned::Unbalanced => return None,
+            Scanned::Close if scanner.depth() == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
 }
 
 fn recognize_input_str(input: &str) -> nom::IResult<&str, &str> {
@@ -84,3 +90,46 @@ impl Deref for StringInline {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod inline_str_tests {
+    use super::*;
+
+    #[test]
+    fn parens() {
+        let input = "(dhsjakdhsjkadhk   dsjakldjsla  )";
+        let (rest, got) = recognize_input_str(input).expect("parse str");
+
+        assert_eq!(rest, "", "nothing remains");
+        assert_eq!(got, "dhsjakdhsjkadhk   dsjakldjsla");
+    }
+
+    #[test]
+    fn anyparens() {
+        let input = "(dhsjakdhsjkadhk   dsjakldjsla  )";
+        let (rest, got) = anyparen(input).expect("parse str");
+
+        assert_eq!(rest, "", "nothing remains");
+        assert_eq!(got, "dhsjakdhsjkadhk   dsjakldjsla");
+    }
+
+    #[test]
+    fn nested_parens() {
+        let (rest, got) = anyparen("(f(g(1), 2)) rest").expect("parse nested");
+        assert_eq!(got, "f(g(1), 2)");
+        assert_eq!(rest, " rest");
+
+        let (rest, got) = anyparen("{ alert(')'); [1, '}'] }").expect("parse quoted");
+        assert_eq!(got, " alert(')'); [1, '}']");
+        assert_eq!(rest, "");
+
+        let (_, got) = anyparen(r#"(say("\")"))"#).expect("parse escaped quote");
+        assert_eq!(got, r#"say("\")")"#);
+
+        let (_, got) = anyparen("(Don't do this)").expect("parse apostrophe");
+        assert_eq!(got, "Don't do this");
+
+        assert!(matches!(anyparen("(f(1)"), Err(nom::Err::Failure(_))));
+        assert!(matches!(anyparen("(f(1])"), Err(nom::Err::Failure(_))));
+    }
+}
@@ -0,0 +1,81 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::multispace0,
+    combinator::{map, opt},
+    multi::many1,
+    sequence::{delimited, preceded, terminated},
+};
+
+use crate::{
+    keywords::*,
+    scan::{Scanned, Scanner},
+    Attribute, AttributeValue, Ident, Parser,
+};
+
+/// Css declarations of the surrounding node.
+///
+/// div {
+///     style { height: 48px; margin-top: 8px; }
+///     p 'hello'
+/// }
+///
+/// Only declarations are accepted, so that style { '...' } still is a style node.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct StyleBlock(pub Vec<Attribute>);
+
+impl Parser for StyleBlock {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        fn declaration(input: &str) -> nom::IResult<&str, Attribute> {
+            let (input, key) = Ident::parse_trim(input)?;
+            let (input, _) = KeywordColon::parse_trim(input)?;
+            let (input, value) = preceded(multispace0, css_value)(input)?;
+            let (input, _) = opt(KeywordNone::parse_trim)(input)?;
+
+            let value = AttributeValue::Expression(value.trim_end().to_string());
+            Ok((
+                input,
+                Attribute {
+                    key,
+                    value: Some(value),
+                },
+            ))
+        }
+
+        map(
+            preceded(
+                terminated(tag("style"), multispace0),
+                delimited(
+                    KeywordCurlyOpen::parse,
+                    many1(declaration),
+                    KeywordCurlyClose::parse_trim,
+                ),
+            ),
+            StyleBlock,
+        )(input)
+    }
+}
+
+/// Everything up to the ; or } ending a declaration.
+/// Those inside of brackets or quotes don't count, e.g. url(data:image/png;base64,...)
+fn css_value(input: &str) -> nom::IResult<&str, &str> {
+    let mut scanner = Scanner::default();
+    let end = input
+        .char_indices()
+        .find(|&(_, c)| {
+            let scanned = scanner.next(c);
+            scanner.depth() == 0
+                && matches!(
+                    (scanned, c),
+                    (Scanned::Plain, ';') | (Scanned::Unbalanced, '}')
+                )
+        })
+        .map_or(input.len(), |(i, _)| i);
+
+    if end == 0 {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::TakeTill1,
+        )));
+    }
+    Ok((&input[end..], &input[..end]))
+}
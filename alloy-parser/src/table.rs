@@ -0,0 +1,147 @@
+use nom::{
+    combinator::{map, opt},
+    multi::separated_list1,
+    sequence::{delimited, terminated},
+};
+
+use crate::{keywords::*, AttributeValue, Body, Element, Ident, List, Node, Parser};
+
+/// Rows of a table
+///
+/// table [[Plan, Price], [Basic, 5€], [Pro, 10€]]
+///
+/// The first row is the header, unless the table is given (header: false).
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Rows(pub Vec<List>);
+
+impl Parser for Rows {
+    fn parse(input: &str) -> nom::IResult<&str, Self> {
+        map(
+            delimited(
+                KeywordBracketOpen::parse,
+                terminated(
+                    separated_list1(KeywordComma::parse_trim, List::parse_trim),
+                    opt(KeywordComma::parse_trim),
+                ),
+                KeywordBracketClose::parse_trim,
+            ),
+            Rows,
+        )(input)
+    }
+}
+
+impl Rows {
+    /// thead and tbody of the table
+    pub fn into_children(self, header: bool) -> Vec<Element> {
+        table_children(self.0.into_iter().map(|row| row.0).collect(), header)
+    }
+}
+
+/// Removes the `header` attribute of a table and tells if the first row is the header.
+pub(crate) fn take_header(table: &mut Node) -> bool {
+    let header = table.take_attribute("header");
+    !matches!(
+        header.and_then(|h| h.value),
+        Some(AttributeValue::Bool(false))
+    )
+}
+
+pub(crate) fn table_children(rows: Vec<Vec<String>>, header: bool) -> Vec<Element> {
+    fn node(kind: &str, body: Body) -> Element {
+        Element::Node(Node {
+            kind: Ident(kind.to_string()),
+            body,
+            ..Default::default()
+        })
+    }
+
+    fn row(cell: &str, cells: Vec<String>) -> Element {
+        let cells = cells
+            .into_iter()
+            .map(|c| node(cell, vec![Element::Text(c)]))
+            .collect();
+        node("tr", cells)
+    }
+
+    let mut rows = rows.into_iter();
+    let mut children = Vec::new();
+
+    if header {
+        if let Some(first) = rows.next() {
+            children.push(node("thead", vec![row("th", first)]));
+        }
+    }
+    children.push(node("tbody", rows.map(|r| row("td", r)).collect()));
+
+    children
+}
+
+/// Reads comma separated values.
+/// Values may be quoted using ", which allows them to contain commas, newlines and "" for a single ".
+pub(crate) fn parse_csv(input: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut value = String::new();
+    let mut quoted = false;
+
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                value.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if value.is_empty() => quoted = true,
+            (',', false) => row.push(std::mem::take(&mut value)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut value));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => value.push(c),
+        }
+    }
+
+    if !value.is_empty() || !row.is_empty() {
+        row.push(value);
+        rows.push(row);
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod table_tests {
+    use super::*;
+
+    #[test]
+    fn csv() {
+        let input = "Plan,Price\r\nBasic,\"5,00 €\"\n\"Pro \"\"plus\"\"\",10\n";
+        let expected = vec![
+            vec!["Plan", "Price"],
+            vec!["Basic", "5,00 €"],
+            vec!["Pro \"plus\"", "10"],
+        ];
+
+        assert_eq!(parse_csv(input), expected);
+        assert_eq!(parse_csv("a,b"), vec![vec!["a", "b"]]);
+    }
+
+    #[test]
+    fn literal() {
+        assert_eq!(
+            Node::from_s("table [[Plan, Price], [Basic, '5€']]"),
+            Node::from_s(
+                "table {
+                    thead tr { th 'Plan' th 'Price' }
+                    tbody tr { td 'Basic' td '5€' }
+                }"
+            )
+        );
+        assert_eq!(
+            Node::from_s("table(header: false) [[a], [b]]"),
+            Node::from_s("table tbody { tr td 'a' tr td 'b' }")
+        );
+    }
+}
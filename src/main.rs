@@ -1,32 +1,128 @@
 use alloy_parser as ast;
+use entities::Entities;
 use std::{
     ffi::OsString,
+    fmt,
     io::{self, stdout, BufWriter, Write},
+    path::PathBuf,
 };
 
 use structopt::StructOpt;
 
+mod entities;
+
 #[derive(StructOpt)]
 /// CLI to transform Alloy files into html
 struct Config {
     #[structopt()]
     infile: OsString,
+
+    /// Compile time constant, e.g. `--define env=prod`. May be given multiple times.
+    #[structopt(
+        short = "D",
+        long = "define",
+        number_of_values = 1,
+        parse(try_from_str = parse_define)
+    )]
+    defines: Vec<(String, String)>,
+
+    /// Toml file with a [define] table. Constants given on the command line take precedence.
+    #[structopt(long = "config", parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    /// What to do with character references like &nbsp;. Either keep or decode.
+    #[structopt(long = "entities", default_value = "keep")]
+    entities: Entities,
+
+    /// Rejects sloppy syntax in all files, as if they started with `#!alloy strict`.
+    #[structopt(long = "strict")]
+    strict: bool,
+}
+
+fn parse_define(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, value)) => Ok((name.trim().to_string(), value.trim().to_string())),
+        None => Err(format!("expected name=value, got {s}")),
+    }
+}
+
+/// Reads the [define] table of a config file
+fn config_defines(path: &PathBuf) -> Result<Vec<(String, String)>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("read config {}: {e}", path.display()))?;
+    let config: toml::Table = content
+        .parse()
+        .map_err(|e| format!("parse config {}: {e}", path.display()))?;
+
+    let Some(defines) = config.get("define").and_then(|d| d.as_table()) else {
+        return Ok(Vec::new());
+    };
+
+    let defines = defines
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                toml::Value::String(s) => s.clone(),
+                v => v.to_string(),
+            };
+            (name.clone(), value)
+        })
+        .collect();
+    Ok(defines)
 }
 
 fn main() {
-    let Config { infile } = Config::from_args();
+    let Config {
+        infile,
+        defines,
+        config,
+        entities,
+        strict,
+    } = Config::from_args();
 
-    let content = std::fs::read_to_string(infile).expect("read input file");
+    let mut expander = ast::Expander::default();
+    expander.strict(strict);
+    let config_defines = match config.as_ref().map(config_defines).transpose() {
+        Ok(config_defines) => config_defines.unwrap_or_default(),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    for (name, value) in config_defines.into_iter().chain(defines) {
+        expander.define(name, value);
+    }
 
-    let node = ast::parse(&content).expect("parse valid input").1;
+    let node = match expander.expand_file(&infile) {
+        Ok(node) => node,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
 
     let out = stdout();
     let out = out.lock();
     let mut out = BufWriter::new(out);
 
-    let config = OutputConfig { indent: 2 };
+    let config = OutputConfig {
+        indent: 2,
+        entities,
+    };
 
-    to_html(&mut out, &node, 0, &config).expect("write to stdout");
+    // #!alloy doctype=html
+    if let Some(doctype) = &expander.pragmas().doctype {
+        writeln!(out, "<!DOCTYPE {doctype}>").expect("write to stdout");
+    }
+
+    match to_html(&mut out, &node, 0, &config, false) {
+        Ok(()) => {}
+        Err(RenderError::Io(e)) => panic!("write to stdout: {}", e),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
 
     out.flush().expect("flush to stdout");
 }
@@ -34,6 +130,7 @@ fn main() {
 #[derive(Debug)]
 struct OutputConfig {
     indent: usize,
+    entities: Entities,
 }
 
 fn to_html(
@@ -41,70 +138,340 @@ fn to_html(
     nodes: &[ast::Element],
     level: usize,
     config: &OutputConfig,
-) -> io::Result<()> {
-    for node in nodes {
-        to_html_node(w, node, level, config)?;
+    inline: bool,
+) -> Result<(), RenderError> {
+    // whether the last element ended with a space of its own
+    let mut spaced = false;
+    for (i, node) in nodes.iter().enumerate() {
+        let layout = if inline {
+            Layout::INLINE
+        } else {
+            // span> 'x' is glued to its siblings
+            let glued_before = i > 0 && trims_outside(&nodes[i - 1]);
+            let glued_after = nodes.get(i + 1).is_some_and(trims_outside);
+            Layout {
+                indent: !trims_outside(node) && !glued_before,
+                newline: !trims_outside(node) && !glued_after,
+                inline: false,
+            }
+        };
+
+        // span>+ 'x' keeps a space to the siblings it's glued to
+        let space = whitespace(node).space_outside;
+        if space && !layout.indent && !spaced {
+            write!(w, " ")?;
+        }
+        to_html_node(w, node, level, config, layout)?;
+        spaced = space && !layout.newline;
+        if spaced {
+            write!(w, " ")?;
+        }
     }
     Ok(())
 }
 
+/// Line breaks and indentation around an element, see `ast::Whitespace`.
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    indent: bool,
+    newline: bool,
+    /// Everything inside is written on the same line.
+    inline: bool,
+}
+
+impl Layout {
+    const BLOCK: Layout = Layout {
+        indent: true,
+        newline: true,
+        inline: false,
+    };
+    const INLINE: Layout = Layout {
+        indent: false,
+        newline: false,
+        inline: true,
+    };
+}
+
+fn whitespace(node: &ast::Element) -> ast::Whitespace {
+    match node {
+        ast::Element::Node(n) => n.whitespace,
+        ast::Element::MarkedText(t) => t.whitespace,
+        _ => ast::Whitespace::default(),
+    }
+}
+
+fn trims_outside(node: &ast::Element) -> bool {
+    whitespace(node).trim_outside
+}
+
 fn to_html_node(
     w: &mut impl Write,
     node: &ast::Element,
     level: usize,
     config: &OutputConfig,
-) -> io::Result<()> {
-    // proper indentation
-    indent(w, level * config.indent)?;
-
+    layout: Layout,
+) -> Result<(), RenderError> {
     let node = match node {
         ast::Element::Text(t) => {
-            let t = escape(&t);
-            writeln!(w, "{t}")?;
+            let t = escape(t, config);
+            return line(w, &t, level, config, layout);
+        }
+        ast::Element::MarkedText(t) => {
+            if layout.indent {
+                indent(w, level * config.indent)?;
+            }
+            to_html(w, &t.body, level, config, true)?;
+            if layout.newline {
+                writeln!(w)?;
+            }
             return Ok(());
         }
+        ast::Element::Raw(r) => return line(w, &r.0, level, config, layout),
+        ast::Element::Comment(c) => {
+            // --> would end the comment early
+            let c = c.0.replace("-->", "--&gt;");
+            return line(w, &format!("<!--{c}-->"), level, config, layout);
+        }
         ast::Element::Node(node) => node,
+        // definitions don't produce any output
+        ast::Element::Component(_)
+        | ast::Element::Include(_)
+        | ast::Element::Import(_)
+        | ast::Element::Extends(_)
+        | ast::Element::Block(_)
+        | ast::Element::If(_) => return Ok(()),
+        // merged into the style attribute of the parent
+        ast::Element::Style(_) => return Ok(()),
     };
 
+    // components are expanded by now, so this one was never defined.
+    // Tag names written in capitals, like DIV, are fine.
+    let name = &node.kind.0;
+    if name.starts_with(|c: char| c.is_ascii_uppercase()) && name.contains(char::is_lowercase) {
+        return Err(RenderError::UndefinedComponent(node.kind.0.clone()));
+    }
+
+    if !node.kind.is_tag_name() {
+        return Err(RenderError::InvalidTagName(node.kind.0.clone()));
+    }
+
+    // proper indentation
+    if layout.indent {
+        indent(w, level * config.indent)?;
+    }
+
     // write start of html tag.
     write!(w, "<{}", node.kind.0)?;
 
-    let mut classes = Vec::new();
+    for (key, value) in html_attributes(node)? {
+        match value {
+            Some(value) => write!(w, " {key}='{}'", escape(&value, config))?,
+            None => write!(w, " {key}")?,
+        }
+    }
+
+    write!(w, ">")?;
+
+    let inline = layout.inline || node.whitespace.trim_inside;
+    if !inline && !node.body.first().is_some_and(trims_outside) {
+        writeln!(w)?;
+    }
+
+    if RAW_TEXT_ELEMENTS.contains(&node.kind.0.as_str()) {
+        // the content of script and style isn't html, so it mustn't be escaped
+        let layout = if inline {
+            Layout::INLINE
+        } else {
+            Layout::BLOCK
+        };
+        for child in node.body.iter() {
+            let ast::Element::Text(t) = child else {
+                to_html_node(w, child, level + 1, config, layout)?;
+                continue;
+            };
+            line(w, t, level + 1, config, layout)?;
+        }
+    } else {
+        to_html(w, &node.body, level + 1, config, inline)?;
+    }
+
+    if !inline && !node.body.last().is_some_and(trims_outside) {
+        indent(w, level * config.indent)?;
+    }
+    write!(w, "</{}>", node.kind.0)?;
+
+    if layout.newline {
+        writeln!(w)?;
+    }
+
+    Ok(())
+}
+
+/// Writes text, that doesn't need any further processing.
+fn line(
+    w: &mut impl Write,
+    text: &str,
+    level: usize,
+    config: &OutputConfig,
+    layout: Layout,
+) -> Result<(), RenderError> {
+    if layout.indent {
+        indent(w, level * config.indent)?;
+    }
+    write!(w, "{text}")?;
+    if layout.newline {
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+enum RenderError {
+    Io(io::Error),
+    /// An attribute was given twice, with different values.
+    Conflict {
+        node: String,
+        attribute: String,
+        values: (String, String),
+    },
+    /// Neither an html element nor a custom element, e.g. my_button
+    InvalidTagName(String),
+    /// A capitalized name, that isn't defined as a component, e.g. Card or MyCard
+    UndefinedComponent(String),
+}
+
+impl From<io::Error> for RenderError {
+    fn from(e: io::Error) -> Self {
+        RenderError::Io(e)
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Io(e) => write!(f, "{e}"),
+            RenderError::Conflict {
+                node,
+                attribute,
+                values: (a, b),
+            } => write!(
+                f,
+                "{node} has conflicting values for {attribute}: '{a}' and '{b}'"
+            ),
+            RenderError::InvalidTagName(name) => write!(
+                f,
+                "{name} is not a valid tag name. Custom elements start with a lowercase letter and need a hyphen"
+            ),
+            RenderError::UndefinedComponent(name) => write!(
+                f,
+                "{name} is not defined. Components need to be defined or imported before they're used"
+            ),
+        }
+    }
+}
+
+/// Attributes of a node, as they are written to html.
+///
+/// Classes from shorthands and attributes are merged, just like style declarations
+/// from attributes and style blocks.
+/// Other attributes, including id, may be given more than once, but only with the same value.
+fn html_attributes(node: &ast::Node) -> Result<Vec<(String, Option<String>)>, RenderError> {
+    let mut attributes: Vec<(String, Option<String>)> = Vec::new();
+    let mut classes: Vec<String> = Vec::new();
+    let mut styles: Vec<String> = Vec::new();
+
+    let set = |attributes: &mut Vec<(String, Option<String>)>,
+               key: &str,
+               value: Option<String>|
+     -> Result<(), RenderError> {
+        let Some((_, existing)) = attributes.iter().find(|(k, _)| k == key) else {
+            attributes.push((key.to_string(), value));
+            return Ok(());
+        };
+        if *existing == value {
+            return Ok(());
+        }
+        Err(RenderError::Conflict {
+            node: node.kind.0.clone(),
+            attribute: key.to_string(),
+            values: (
+                existing.clone().unwrap_or_default(),
+                value.unwrap_or_default(),
+            ),
+        })
+    };
+
+    // class and style keep the position they're first seen at, values are filled in afterwards
+    let reserve = |attributes: &mut Vec<(String, Option<String>)>, key: &str| {
+        if !attributes.iter().any(|(k, _)| k == key) {
+            attributes.push((key.to_string(), None));
+        }
+    };
+
     for ioc in node.ids_and_classes.iter() {
         match ioc {
-            ast::IdOrClass::Id(i) => {
-                write!(w, " id='{}'", i.0)?;
-            }
+            ast::IdOrClass::Id(i) => set(&mut attributes, "id", Some(i.0.clone()))?,
             ast::IdOrClass::Class(c) => {
+                reserve(&mut attributes, "class");
                 classes.push(c.0.clone());
             }
         }
     }
 
-    if !classes.is_empty() {
-        let classes = classes.join(" ");
+    let attrs = node.attributes.iter().flat_map(|attrs| attrs.0.iter());
+    for attr in attrs {
+        let key = attr.key.0.as_str();
+        let value = match attr.value {
+            // boolean attributes are on by being present, regardless of their value.
+            // Others, like draggable or aria-hidden, keep true and false as their value.
+            Some(ast::AttributeValue::Bool(false)) if BOOLEAN_ATTRIBUTES.contains(&key) => continue,
+            Some(ast::AttributeValue::Bool(true)) if BOOLEAN_ATTRIBUTES.contains(&key) => None,
+            None => None,
+            Some(ref value) => Some(value.to_string()),
+        };
 
-        write!(w, " class='{classes}'")?;
+        match (key, value) {
+            ("class", Some(value)) => {
+                reserve(&mut attributes, "class");
+                classes.extend(value.split_whitespace().map(String::from));
+            }
+            ("style", Some(value)) => {
+                reserve(&mut attributes, "style");
+                let declarations = value.trim().trim_end_matches(';').trim_end();
+                if !declarations.is_empty() {
+                    styles.push(declarations.to_string());
+                }
+            }
+            (key, value) => set(&mut attributes, key, value)?,
+        }
     }
 
-    if let Some(ref attrs) = node.attributes {
-        for attr in attrs.0.iter() {
-            write!(w, " {}", attr.key.0)?;
-            let Some(ref value) = attr.value else {
-                continue;
-            };
-            write!(w, "='{}'", escape(value))?;
+    // style { ... } blocks inside the body come last
+    for child in node.body.iter() {
+        if let ast::Element::Style(style) = child {
+            reserve(&mut attributes, "style");
+            styles.push(ast::AttributeValue::Map(style.0.clone()).to_string());
         }
     }
 
-    writeln!(w, ">")?;
-
-    to_html(w, &node.body, level + 1, config)?;
+    let mut unique = Vec::new();
+    for class in classes {
+        if !unique.contains(&class) {
+            unique.push(class);
+        }
+    }
 
-    indent(w, level * config.indent)?;
-    writeln!(w, "</{}>", node.kind.0)?;
+    for (key, value) in attributes.iter_mut() {
+        match key.as_str() {
+            "class" if !unique.is_empty() => *value = Some(unique.join(" ")),
+            "style" if !styles.is_empty() => *value = Some(styles.join("; ")),
+            _ => {}
+        }
+    }
+    // e.g. class: ''
+    attributes.retain(|(key, value)| value.is_some() || !matches!(key.as_str(), "class" | "style"));
 
-    Ok(())
+    Ok(attributes)
 }
 
 fn indent(w: &mut impl Write, times: usize) -> io::Result<()> {
@@ -115,13 +482,68 @@ fn indent(w: &mut impl Write, times: usize) -> io::Result<()> {
     Ok(())
 }
 
-fn escape(i: &str) -> String {
+/// Attributes that are switched on by being present, e.g. disabled.
+const BOOLEAN_ATTRIBUTES: [&str; 25] = [
+    "allowfullscreen",
+    "async",
+    "autofocus",
+    "autoplay",
+    "checked",
+    "controls",
+    "default",
+    "defer",
+    "disabled",
+    "formnovalidate",
+    "hidden",
+    "inert",
+    "ismap",
+    "itemscope",
+    "loop",
+    "multiple",
+    "muted",
+    "nomodule",
+    "novalidate",
+    "open",
+    "playsinline",
+    "readonly",
+    "required",
+    "reversed",
+    "selected",
+];
+
+/// Elements whose text content is written without escaping.
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+/// Escapes text and attribute values.
+/// Character references are kept or decoded, depending on the config.
+fn escape(i: &str, config: &OutputConfig) -> String {
     let mut s = String::new();
-    for c in i.chars() {
+    let mut rest = i;
+    while let Some(c) = rest.chars().next() {
+        let mut len = c.len_utf8();
         match c {
-            '\'' => {
-                s.push_str("\\'");
-            }
+            '&' => match entities::reference(rest) {
+                Some((reference_len, Some(decoded))) => {
+                    len = reference_len;
+                    match config.entities {
+                        Entities::Keep => s.push_str(&rest[..len]),
+                        // &lt; must still be escaped after decoding
+                        Entities::Decode => s.push_str(&escape(&decoded, config)),
+                    }
+                }
+                Some((reference_len, None)) => {
+                    eprintln!(
+                        "warning: unknown character reference {}",
+                        &rest[..reference_len]
+                    );
+                    s.push_str("&amp;");
+                }
+                None => s.push_str("&amp;"),
+            },
+            '<' => s.push_str("&lt;"),
+            '>' => s.push_str("&gt;"),
+            '\'' => s.push_str("&#39;"),
+            '"' => s.push_str("&quot;"),
             // '\n' => {
             //     s.push_str("<br />");
             // }
@@ -129,7 +551,145 @@ fn escape(i: &str) -> String {
                 s.push(x);
             }
         };
+        rest = &rest[len..];
     }
 
     s
 }
+
+#[cfg(test)]
+mod render_tests {
+    use super::*;
+    use ast::Parser;
+
+    fn attributes(node: &str) -> Result<Vec<(String, Option<String>)>, RenderError> {
+        html_attributes(&ast::Node::from_s(node))
+    }
+
+    fn owned(attributes: &[(&str, Option<&str>)]) -> Vec<(String, Option<String>)> {
+        attributes
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.map(String::from)))
+            .collect()
+    }
+
+    fn render(s: &str) -> Result<String, RenderError> {
+        let config = OutputConfig {
+            indent: 2,
+            entities: Entities::Keep,
+        };
+        let (_, body) = ast::parse(s).expect("parse");
+        let mut out = Vec::new();
+        to_html(&mut out, &body, 0, &config, false)?;
+        Ok(String::from_utf8(out).expect("utf8"))
+    }
+
+    #[test]
+    fn merged_attributes() {
+        let attrs = attributes(
+            "div#a.x(class: [x, y], style: 'a: 1;', id: a, style: 'b: 2', hidden: false, d);",
+        )
+        .expect("no conflicts");
+        let expected = [
+            ("id", Some("a")),
+            ("class", Some("x y")),
+            ("style", Some("a: 1; b: 2")),
+            ("d", None),
+        ];
+
+        assert_eq!(attrs, owned(&expected));
+        assert_eq!(
+            attributes("div(class: '', style: ' ', title: '');").expect("no conflicts"),
+            vec![(String::from("title"), Some(String::new()))]
+        );
+    }
+
+    #[test]
+    fn merged_styles() {
+        let attrs =
+            attributes("div(style: 'a: 1', style: (b: 2, c: 3px)) { style { d: 3; e: 4 } p 'x' }")
+                .expect("no conflicts");
+        let expected = vec![(
+            String::from("style"),
+            Some(String::from("a: 1; b: 2; c: 3px; d: 3; e: 4")),
+        )];
+
+        assert_eq!(attrs, expected);
+    }
+
+    #[test]
+    fn grouped_attributes() {
+        let attrs = attributes("button(aria: (label: 'Close', hidden: false), data: (id: 7));")
+            .expect("no conflicts");
+        let expected = [
+            ("aria-label", Some("Close")),
+            ("aria-hidden", Some("false")),
+            ("data-id", Some("7")),
+        ];
+
+        assert_eq!(attrs, owned(&expected));
+    }
+
+    #[test]
+    fn whitespace_control() {
+        let render = |s: &str| render(s).expect("render");
+
+        assert_eq!(render("p { a< 'x' }"), "<p>\n  <a>x</a>\n</p>\n");
+        assert_eq!(
+            render("p { 'a' span> 'b' 'c' }"),
+            "<p>\n  a<span>\n    b\n  </span>c\n</p>\n"
+        );
+        assert_eq!(render("p { span<> 'b' }"), "<p><span>b</span></p>\n");
+        assert_eq!(
+            render("#!alloy edition=2\np { 'a' span>+ 'b' 'c'> '*d*'> }"),
+            "<p>\n  a <span>\n    b\n  </span> c<em>d</em></p>\n"
+        );
+        assert_eq!(
+            render("p< { a>+ 'x' b>+ 'y' }"),
+            "<p> <a>x</a> <b>y</b> </p>\n"
+        );
+    }
+
+    #[test]
+    fn tag_names() {
+        assert!(render("mein-knöpfchen#größe;").is_ok());
+        assert!(matches!(
+            render("my_button;"),
+            Err(RenderError::InvalidTagName(_))
+        ));
+        assert!(render("svg { font-face; }").is_ok());
+        assert!(render("DIV;").is_ok());
+        assert!(matches!(
+            render("Card;"),
+            Err(RenderError::UndefinedComponent(_))
+        ));
+    }
+
+    #[test]
+    fn boolean_attributes() {
+        let attrs = attributes(
+            "input(disabled: true, checked: false, draggable: false, spellcheck: true, hx-boost: true);",
+        )
+        .expect("no conflicts");
+        let expected = [
+            ("disabled", None),
+            ("draggable", Some("false")),
+            ("spellcheck", Some("true")),
+            ("hx-boost", Some("true")),
+        ];
+
+        assert_eq!(attrs, owned(&expected));
+    }
+
+    #[test]
+    fn conflicting_attributes() {
+        assert!(matches!(
+            attributes("div#a(id: b);"),
+            Err(RenderError::Conflict { .. })
+        ));
+        assert!(matches!(
+            attributes("input(type: text, type: checkbox);"),
+            Err(RenderError::Conflict { .. })
+        ));
+    }
+}